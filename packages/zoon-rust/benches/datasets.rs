@@ -0,0 +1,158 @@
+//! Rust ports of the generators in `apps/benchmark/src/datasets.ts`, seeded so
+//! every run measures the same data.
+
+use serde_json::{json, Value};
+
+const DEPARTMENTS: &[&str] = &["Engineering", "Sales", "Marketing", "HR", "Finance", "Operations"];
+const ROLES: &[&str] = &["admin", "user", "guest", "manager"];
+const CATEGORIES: &[&str] = &["Electronics", "Clothing", "Books", "Home", "Sports", "Food"];
+const STATUSES: &[&str] = &["pending", "processing", "shipped", "delivered", "cancelled"];
+const EVENT_TYPES: &[&str] = &["page_view", "click", "scroll", "submit", "error"];
+const PAGES: &[&str] = &["/home", "/products", "/checkout", "/profile", "/settings"];
+const LOCATIONS: &[&str] = &["Building_A", "Building_B", "Warehouse_1", "Warehouse_2", "Office"];
+const FIRST_NAMES: &[&str] = &["Alice", "Bob", "Carol", "Dave", "Eve", "Frank", "Grace", "Henry", "Ivy", "Jack"];
+const LAST_NAMES: &[&str] = &[
+    "Smith", "Johnson", "Williams", "Brown", "Jones", "Garcia", "Miller", "Davis", "Rodriguez", "Martinez",
+];
+const PRODUCT_NAMES: &[&str] = &[
+    "Widget Pro", "Gadget X", "Smart Device", "Ultra Cable", "Power Bank",
+    "Wireless Hub", "Digital Display", "Sensor Kit", "Controller V2", "Adapter Plus",
+];
+
+/// Small linear congruential generator; quality does not matter here.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+
+    fn int(&mut self, min: i64, max: i64) -> i64 {
+        min + (self.next() % (max - min + 1) as u64) as i64
+    }
+
+    fn chance(&mut self, p: f64) -> bool {
+        (self.next() % 1000) as f64 / 1000.0 < p
+    }
+
+    fn choice<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.next() as usize % items.len()]
+    }
+
+    fn date(&mut self, year_from: i64, year_to: i64) -> String {
+        format!("{}-{:02}-{:02}", self.int(year_from, year_to), self.int(1, 12), self.int(1, 28))
+    }
+
+    fn timestamp(&mut self) -> String {
+        format!(
+            "2024-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            self.int(1, 12),
+            self.int(1, 28),
+            self.int(0, 23),
+            self.int(0, 59),
+            self.int(0, 59),
+            self.int(0, 999)
+        )
+    }
+
+    fn name(&mut self) -> String {
+        format!("{} {}", self.choice(FIRST_NAMES), self.choice(LAST_NAMES))
+    }
+}
+
+pub fn employees(count: usize) -> Value {
+    let mut rng = Rng(1);
+    (1..=count)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": rng.name(),
+                "email": format!("user{}@company.com", i),
+                "department": rng.choice(DEPARTMENTS),
+                "role": rng.choice(ROLES),
+                "salary": rng.int(40000, 150000),
+                "active": !rng.chance(0.1),
+                "hireDate": rng.date(2015, 2024),
+            })
+        })
+        .collect()
+}
+
+pub fn products(count: usize) -> Value {
+    let mut rng = Rng(2);
+    (1..=count)
+        .map(|i| {
+            json!({
+                "id": i,
+                "name": format!("{} {}", rng.choice(PRODUCT_NAMES), i),
+                "category": rng.choice(CATEGORIES),
+                "price": rng.int(999, 99999) as f64 / 100.0,
+                "stock": rng.int(0, 500),
+                "active": !rng.chance(0.15),
+            })
+        })
+        .collect()
+}
+
+pub fn orders(count: usize) -> Value {
+    let mut rng = Rng(3);
+    (1..=count)
+        .map(|i| {
+            json!({
+                "id": i,
+                "customerId": rng.int(1, 100),
+                "customerName": rng.name(),
+                "status": rng.choice(STATUSES),
+                "total": rng.int(1000, 50000) as f64 / 100.0,
+                "items": rng.int(1, 10),
+                "createdAt": rng.date(2024, 2024),
+            })
+        })
+        .collect()
+}
+
+pub fn analytics(count: usize) -> Value {
+    let mut rng = Rng(4);
+    (1..=count)
+        .map(|i| {
+            json!({
+                "id": i,
+                "eventType": rng.choice(EVENT_TYPES),
+                "userId": format!("user_{}", rng.int(1, 50)),
+                "page": rng.choice(PAGES),
+                "timestamp": rng.timestamp(),
+                "duration": rng.int(100, 30000),
+                "successful": !rng.chance(0.05),
+            })
+        })
+        .collect()
+}
+
+pub fn sensors(count: usize) -> Value {
+    let mut rng = Rng(5);
+    (1..=count)
+        .map(|i| {
+            json!({
+                "id": i,
+                "sensorId": format!("sensor_{}", rng.int(1, 10)),
+                "location": rng.choice(LOCATIONS),
+                "temperature": rng.int(150, 350) as f64 / 10.0,
+                "humidity": rng.int(30, 80),
+                "pressure": rng.int(980, 1030),
+                "timestamp": rng.timestamp(),
+            })
+        })
+        .collect()
+}
+
+/// Every dataset at the given row count, by name.
+pub fn all(count: usize) -> Vec<(&'static str, Value)> {
+    vec![
+        ("employees", employees(count)),
+        ("products", products(count)),
+        ("orders", orders(count)),
+        ("analytics", analytics(count)),
+        ("sensors", sensors(count)),
+    ]
+}
@@ -0,0 +1,40 @@
+//! Encode and decode throughput of ZOON against `serde_json` on the benchmark
+//! datasets.
+//!
+//! ```bash
+//! cargo bench --bench throughput
+//! ```
+
+mod datasets;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use serde_json::Value;
+use std::hint::black_box;
+
+const ROWS: usize = 1_000;
+
+fn decode(c: &mut Criterion) {
+    for (name, value) in datasets::all(ROWS) {
+        let zoon = zoon_format::encode(&value).unwrap();
+        let json = serde_json::to_string(&value).unwrap();
+
+        let mut group = c.benchmark_group(format!("decode/{}", name));
+        group.throughput(Throughput::Elements(ROWS as u64));
+        group.bench_function("zoon", |b| b.iter(|| zoon_format::decode::<Value>(black_box(&zoon)).unwrap()));
+        group.bench_function("serde_json", |b| b.iter(|| serde_json::from_str::<Value>(black_box(&json)).unwrap()));
+        group.finish();
+    }
+}
+
+fn encode(c: &mut Criterion) {
+    for (name, value) in datasets::all(ROWS) {
+        let mut group = c.benchmark_group(format!("encode/{}", name));
+        group.throughput(Throughput::Elements(ROWS as u64));
+        group.bench_function("zoon", |b| b.iter(|| zoon_format::encode(black_box(&value)).unwrap()));
+        group.bench_function("serde_json", |b| b.iter(|| serde_json::to_string(black_box(&value)).unwrap()));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, decode, encode);
+criterion_main!(benches);
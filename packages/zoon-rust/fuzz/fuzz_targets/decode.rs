@@ -0,0 +1,10 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Any input may fail to decode, but must never panic or exhaust memory.
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = zoon_format::decode::<serde_json::Value>(input);
+    }
+});
@@ -0,0 +1,38 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use serde_json::{json, Value};
+
+#[derive(Arbitrary, Debug)]
+struct Row {
+    count: i64,
+    score: Option<i32>,
+    active: bool,
+    name: String,
+    status: u8,
+    tags: Vec<u16>,
+    nested: Option<(i32, String)>,
+}
+
+fn to_value(row: &Row) -> Value {
+    let status = ["open", "closed", "pending"][row.status as usize % 3];
+    json!({
+        "count": row.count,
+        "score": row.score,
+        "active": row.active,
+        "name": row.name,
+        "status": status,
+        "tags": row.tags,
+        "meta": row.nested.as_ref().map_or(json!({"n": 0, "label": ""}), |(n, label)| json!({"n": n, "label": label})),
+    })
+}
+
+// Encoding never fails for arrays of objects, and the output decodes back
+// to the same value whatever the strings hold.
+fuzz_target!(|rows: Vec<Row>| {
+    let value = Value::Array(rows.iter().map(to_value).collect());
+    let encoded = zoon_format::encode(&value).expect("arrays of objects always encode");
+    let decoded = zoon_format::decode::<Value>(&encoded).expect("encoder output decodes");
+    assert_eq!(decoded, value, "encoded: {}", encoded);
+});
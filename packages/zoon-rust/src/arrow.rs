@@ -0,0 +1,617 @@
+//! Apache Arrow `RecordBatch` interop (feature `arrow`).
+//!
+//! Columns are converted directly to tabular ZOON cells, without building row
+//! objects. Struct columns become dotted fields (aliased like any other nested
+//! field), and string dictionaries become enums.
+
+use crate::decode::{is_header_line, quoted_text, read_header, DecodeOptions, HeaderField, DEFAULT_CELL};
+use crate::encode::{delta_cells, format_str_cell, needs_quotes, sequence_type, write_table, TableColumn};
+use crate::{Result, ZoonError};
+use arrow_array::builder::{BooleanBuilder, StringBuilder, StringDictionaryBuilder};
+use arrow_array::cast::AsArray;
+use arrow_array::types::*;
+use arrow_array::{Array, ArrayRef, ArrowPrimitiveType, PrimitiveArray, RecordBatch, RecordBatchOptions, StringArray, StructArray};
+use arrow_schema::{DataType, Field, Fields, Schema, SchemaRef};
+use std::sync::Arc;
+
+/// Encodes a `RecordBatch` as tabular ZOON.
+///
+/// Integer columns map to `i`, booleans to `b`, strings to `s` (or `t` when
+/// long or when some need quotes) and string dictionaries to enums. Floats share the numeric `i` type
+/// and are read back as `Float64` when a column holds decimals.
+pub fn record_batch_to_zoon(batch: &RecordBatch) -> Result<String> {
+    let rows = batch.num_rows();
+    if rows == 0 {
+        return Ok(String::new());
+    }
+
+    let mut leaves = Vec::new();
+    let valid = vec![true; rows];
+    for (field, array) in batch.schema().fields().iter().zip(batch.columns()) {
+        collect_leaves(field.name(), array, &valid, &mut leaves)?;
+    }
+
+    let mut constants = Vec::new();
+    let mut columns = Vec::new();
+    for leaf in leaves {
+        match leaf.constant(rows) {
+            Some(value) => constants.push((leaf.column.name, value)),
+            None => columns.push(leaf.column),
+        }
+    }
+
+    Ok(write_table(&constants, &columns, rows, None))
+}
+
+/// Decodes tabular ZOON into a `RecordBatch`, deriving the schema from the
+/// header: `i` becomes `Int64`, `b` `Boolean`, enums `Dictionary(Int32, Utf8)`
+/// and everything else `Utf8`. Dotted fields are grouped into struct columns.
+pub fn zoon_to_record_batch(input: &str) -> Result<RecordBatch> {
+    let table = read_table(input)?;
+    let mut fields = Vec::new();
+    let mut arrays = Vec::new();
+    for node in group_by_prefix(table.columns.iter().map(|c| c.name.as_str())) {
+        let (field, array) = build_inferred(&node, "", &table)?;
+        fields.push(field);
+        arrays.push(array);
+    }
+    finish(Arc::new(Schema::new(fields)), arrays, table.rows)
+}
+
+/// Decodes tabular ZOON into a `RecordBatch` with the given schema.
+///
+/// Struct fields are looked up by their dotted path. Columns missing from the
+/// document are filled with nulls.
+pub fn zoon_to_record_batch_with_schema(input: &str, schema: SchemaRef) -> Result<RecordBatch> {
+    let table = read_table(input)?;
+    let mut arrays = Vec::new();
+    for field in schema.fields() {
+        arrays.push(build_with_field(field, field.name(), &table)?);
+    }
+    finish(schema, arrays, table.rows)
+}
+
+fn finish(schema: SchemaRef, arrays: Vec<ArrayRef>, rows: usize) -> Result<RecordBatch> {
+    let options = RecordBatchOptions::new().with_row_count(Some(rows));
+    RecordBatch::try_new_with_options(schema, arrays, &options).map_err(|e| ZoonError::ParseError(e.to_string()))
+}
+
+struct Leaf {
+    column: TableColumn,
+    options: Vec<String>,
+}
+
+impl Leaf {
+    /// Returns the hoisted `@` value when every row holds the same non-null cell.
+    fn constant(&self, rows: usize) -> Option<String> {
+        let cells = &self.column.cells;
+        let first = cells.first()?;
+        if rows < 2 || first == "~" || first == "\"\"" || cells.iter().any(|c| c != first) {
+            return None;
+        }
+        match self.column.type_code.as_str() {
+            "i" => Some(format!(":{}", first)),
+            "b" => Some(format!(":{}", if first == "1" { "y" } else { "n" })),
+            "s" => Some(format!("={}", first)),
+            code if code.starts_with('=') => Some(format!("={}", first)),
+            code if code.starts_with('!') => Some(format!("={}", self.options[first.parse::<usize>().ok()?])),
+            _ => None,
+        }
+    }
+}
+
+fn collect_leaves(name: &str, array: &ArrayRef, parent_valid: &[bool], out: &mut Vec<Leaf>) -> Result<()> {
+    let valid: Vec<bool> = (0..array.len()).map(|i| parent_valid[i] && array.is_valid(i)).collect();
+    let cells = |f: &dyn Fn(usize) -> String| -> Vec<String> {
+        (0..array.len()).map(|i| if valid[i] { f(i) } else { "~".to_string() }).collect()
+    };
+    let leaf = |type_code: &str, cells: Vec<String>| Leaf {
+        column: TableColumn { name: name.to_string(), type_code: type_code.to_string(), cells },
+        options: Vec::new(),
+    };
+
+    macro_rules! int_leaf {
+        ($t:ty) => {{
+            let values = array.as_primitive::<$t>();
+            let cells = cells(&|i| values.value(i).to_string());
+            match sequence_type(name, &cells, None) {
+                Some(code) if code == "i^" => leaf(&code, delta_cells(&cells)),
+                Some(code) => leaf(&code, Vec::new()),
+                None => leaf("i", cells),
+            }
+        }};
+    }
+    macro_rules! float_leaf {
+        ($t:ty) => {{
+            let values = array.as_primitive::<$t>();
+            let cells = cells(&|i| {
+                let v = values.value(i);
+                // Spec 3.3: NaN and infinities encode as null.
+                if v.is_finite() { v.to_string() } else { "~".to_string() }
+            });
+            leaf("i", cells)
+        }};
+    }
+
+    let leaf = match array.data_type() {
+        DataType::Struct(_) => {
+            let structs = array.as_struct();
+            for (field, child) in structs.fields().iter().zip(structs.columns()) {
+                collect_leaves(&format!("{}.{}", name, field.name()), child, &valid, out)?;
+            }
+            return Ok(());
+        }
+        DataType::Null => leaf("s", cells(&|_| "~".to_string())),
+        DataType::Boolean => {
+            let values = array.as_boolean();
+            leaf("b", cells(&|i| if values.value(i) { "1".into() } else { "0".into() }))
+        }
+        DataType::Int8 => int_leaf!(Int8Type),
+        DataType::Int16 => int_leaf!(Int16Type),
+        DataType::Int32 => int_leaf!(Int32Type),
+        DataType::Int64 => int_leaf!(Int64Type),
+        DataType::UInt8 => int_leaf!(UInt8Type),
+        DataType::UInt16 => int_leaf!(UInt16Type),
+        DataType::UInt32 => int_leaf!(UInt32Type),
+        DataType::UInt64 => int_leaf!(UInt64Type),
+        DataType::Float32 => float_leaf!(Float32Type),
+        DataType::Float64 => float_leaf!(Float64Type),
+        DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View => {
+            let strings = string_values(array)?;
+            let type_code = text_type(&strings);
+            leaf(type_code, cells(&|i| format_str_cell(strings[i].unwrap_or_default(), type_code)))
+        }
+        DataType::Dictionary(_, value_type) if is_string_type(value_type) => {
+            let dict = array.as_any_dictionary();
+            let values = string_values(dict.values())?;
+            let keys = dict.normalized_keys();
+            // Options that are empty, hold `|` or need quotes can't be listed in the header.
+            if values.iter().any(|v| v.is_none_or(|v| v.is_empty() || v.contains('|') || needs_quotes(v))) {
+                let strings: Vec<Option<&str>> = keys.iter().map(|&k| values.get(k).copied().flatten()).collect();
+                let type_code = text_type(&strings);
+                leaf(type_code, cells(&|i| format_str_cell(strings[i].unwrap_or_default(), type_code)))
+            } else {
+                let options: Vec<String> = values.iter().map(|v| v.unwrap_or_default().replace(' ', "_")).collect();
+                let avg_len = options.iter().map(|o| o.len()).sum::<usize>() / options.len().max(1);
+                // Same trade-off as the row encoder: indices beat literals for 3+ long options.
+                let indexed = options.len() >= 3 && avg_len * array.len() > options.join("|").len() + array.len() * 2;
+                let (type_code, cells) = if indexed {
+                    (format!("!{}", options.join("|")), cells(&|i| keys[i].to_string()))
+                } else {
+                    (format!("={}", options.join("|")), cells(&|i| options[keys[i]].clone()))
+                };
+                Leaf { options, ..leaf(&type_code, cells) }
+            }
+        }
+        other => {
+            return Err(ZoonError::UnsupportedType(format!("arrow type {} in column {}", other, name)));
+        }
+    };
+    out.push(leaf);
+    Ok(())
+}
+
+fn is_string_type(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View)
+}
+
+fn string_values(array: &ArrayRef) -> Result<Vec<Option<&str>>> {
+    Ok(match array.data_type() {
+        DataType::Utf8 => array.as_string::<i32>().iter().collect(),
+        DataType::LargeUtf8 => array.as_string::<i64>().iter().collect(),
+        DataType::Utf8View => array.as_string_view().iter().collect(),
+        other => return Err(ZoonError::UnsupportedType(format!("arrow dictionary values of type {}", other))),
+    })
+}
+
+fn text_type(strings: &[Option<&str>]) -> &'static str {
+    if strings.iter().flatten().any(|s| needs_quotes(s)) {
+        return "t";
+    }
+    let total: usize = strings.iter().map(|s| s.map_or(1, str::len)).sum();
+    if !strings.is_empty() && total / strings.len() > 30 {
+        "t"
+    } else {
+        "s"
+    }
+}
+
+/// Decoded cells of one header field or constant.
+struct DecodedColumn {
+    name: String,
+    typ: String,
+    options: Vec<String>,
+    values: Vec<Option<String>>,
+}
+
+struct Table {
+    columns: Vec<DecodedColumn>,
+    rows: usize,
+}
+
+impl Table {
+    fn column(&self, name: &str) -> Option<&DecodedColumn> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+}
+
+fn read_table(input: &str) -> Result<Table> {
+    let (header, lines) = read_header(input, &DecodeOptions::default())?;
+
+    let mut tokens: Vec<Vec<String>> = header.fields.iter().map(|_| Vec::new()).collect();
+    let mut rows = 0;
+    let mut push = |line: &str, index: i64| -> Result<()> {
+        for (column, token) in tokens.iter_mut().zip(header.split_row(line, index)?) {
+            column.push(token.into_owned());
+        }
+        Ok(())
+    };
+    for _ in 0..header.explicit_rows {
+        push("", rows as i64)?;
+        rows += 1;
+    }
+    for line in lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if is_header_line(line) {
+            return Err(ZoonError::UnsupportedType("multiple headers cannot share one record batch".into()));
+        }
+        push(line, rows as i64)?;
+        rows += 1;
+    }
+
+    let mut columns = Vec::new();
+    for c in &header.constants {
+        columns.push(DecodedColumn {
+            name: c.name.clone(),
+            typ: if c.typ == "s" { "s".to_string() } else { infer_constant_type(&c.val).to_string() },
+            options: Vec::new(),
+            values: vec![Some(c.val.clone()); rows],
+        });
+    }
+    for (field, tokens) in header.fields.iter().zip(tokens) {
+        let tokens = if field.typ == "i^" { running_totals(field, tokens)? } else { tokens };
+        let values = tokens
+            .iter()
+            .map(|t| decode_token(field, t))
+            .collect::<Result<Vec<_>>>()?;
+        // `i` columns hold any number; decimals make them floats.
+        let has_decimals = field.typ == "i" && values.iter().flatten().any(|v| v.parse::<i64>().is_err());
+        columns.push(DecodedColumn {
+            name: field.name.clone(),
+            typ: if has_decimals { "f".to_string() } else { field.typ.clone() },
+            options: field.options.iter().map(|o| o.replace('_', " ")).collect(),
+            values,
+        });
+    }
+    Ok(Table { columns, rows })
+}
+
+/// Adds up the differences in a delta (`i^`) column.
+fn running_totals(field: &HeaderField, tokens: Vec<String>) -> Result<Vec<String>> {
+    let mut total = 0i64;
+    tokens
+        .into_iter()
+        .map(|token| {
+            if token == "~" {
+                return Ok(token);
+            }
+            total = total
+                .checked_add(field.delta(&token)?)
+                .ok_or_else(|| ZoonError::ParseError(format!("delta overflow in {}", field.name)))?;
+            Ok(total.to_string())
+        })
+        .collect()
+}
+
+fn infer_constant_type(val: &str) -> &'static str {
+    if val == "y" || val == "n" {
+        "b"
+    } else if val.parse::<i64>().is_ok() {
+        "i"
+    } else if val.parse::<f64>().is_ok() {
+        "f"
+    } else {
+        "s"
+    }
+}
+
+/// Turns a raw row token into the field's text value.
+fn decode_token(field: &HeaderField, token: &str) -> Result<Option<String>> {
+    if token == "~" {
+        return Ok(None);
+    }
+    if token.starts_with('"') {
+        return Ok(Some(field.prefix.clone() + &quoted_text(token)));
+    }
+    if let (DEFAULT_CELL, Some(default)) = (token, &field.default) {
+        return Ok(match default {
+            serde_json::Value::Null => None,
+            serde_json::Value::String(s) => Some(s.clone()),
+            serde_json::Value::Bool(b) => Some(if *b { "1" } else { "0" }.to_string()),
+            other => Some(other.to_string()),
+        });
+    }
+    let text = match field.typ.as_str() {
+        "e" => field.option(token)?.replace('_', " "),
+        "d" | "dt" | "du" => field.temporal(token)?,
+        "u" | "x" => field.identifier(token)?,
+        "i" | "i+" | "i^" | "r" | "b" => token.to_string(),
+        _ => token.replace('_', " "),
+    };
+    Ok(Some(field.prefix.clone() + &text))
+}
+
+/// A top-level column or struct child, in first-seen order.
+struct Node {
+    name: String,
+    children: Vec<Node>,
+}
+
+fn group_by_prefix<'a>(names: impl Iterator<Item = &'a str>) -> Vec<Node> {
+    let mut roots: Vec<Node> = Vec::new();
+    for name in names {
+        let mut level = &mut roots;
+        for part in name.split('.') {
+            let idx = match level.iter().position(|n| n.name == part) {
+                Some(idx) => idx,
+                None => {
+                    level.push(Node { name: part.to_string(), children: Vec::new() });
+                    level.len() - 1
+                }
+            };
+            level = &mut level[idx].children;
+        }
+    }
+    roots
+}
+
+fn build_inferred(node: &Node, prefix: &str, table: &Table) -> Result<(Field, ArrayRef)> {
+    let path = if prefix.is_empty() { node.name.clone() } else { format!("{}.{}", prefix, node.name) };
+    if node.children.is_empty() {
+        let column = table
+            .column(&path)
+            .ok_or_else(|| ZoonError::InvalidFormat(format!("missing column {}", path)))?;
+        let data_type = match column.typ.as_str() {
+            "i" | "i+" | "i^" | "r" => DataType::Int64,
+            "b" => DataType::Boolean,
+            "f" => DataType::Float64,
+            _ if !column.options.is_empty() => {
+                DataType::Dictionary(Box::new(DataType::Int32), Box::new(DataType::Utf8))
+            }
+            _ => DataType::Utf8,
+        };
+        let field = Field::new(&node.name, data_type, true);
+        let array = build_array(column, field.data_type())?;
+        return Ok((field, array));
+    }
+
+    if table.column(&path).is_some() {
+        return Err(ZoonError::UnsupportedType(format!("column {} is also a struct of dotted fields", path)));
+    }
+    let mut fields = Vec::new();
+    let mut arrays = Vec::new();
+    for child in &node.children {
+        let (field, array) = build_inferred(child, &path, table)?;
+        fields.push(field);
+        arrays.push(array);
+    }
+    let array = StructArray::try_new(Fields::from(fields), arrays, None).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+    Ok((Field::new(&node.name, array.data_type().clone(), true), Arc::new(array)))
+}
+
+fn build_with_field(field: &Field, path: &str, table: &Table) -> Result<ArrayRef> {
+    if let DataType::Struct(children) = field.data_type() {
+        let mut arrays = Vec::new();
+        for child in children {
+            arrays.push(build_with_field(child, &format!("{}.{}", path, child.name()), table)?);
+        }
+        let array = StructArray::try_new(children.clone(), arrays, None).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+        return Ok(Arc::new(array));
+    }
+
+    match table.column(path) {
+        Some(column) => build_array(column, field.data_type()),
+        None if field.is_nullable() => Ok(arrow_array::new_null_array(field.data_type(), table.rows)),
+        None => Err(ZoonError::InvalidFormat(format!("missing non-nullable column {}", path))),
+    }
+}
+
+fn build_array(column: &DecodedColumn, data_type: &DataType) -> Result<ArrayRef> {
+    let values = &column.values;
+    let parse_err = |v: &str| ZoonError::ParseError(format!("invalid {} value {} in column {}", data_type, v, column.name));
+
+    fn primitive<T: ArrowPrimitiveType>(
+        values: &[Option<String>],
+        err: &dyn Fn(&str) -> ZoonError,
+    ) -> Result<ArrayRef>
+    where
+        T::Native: std::str::FromStr,
+    {
+        let parsed = values
+            .iter()
+            .map(|v| v.as_deref().map(|s| s.parse::<T::Native>().map_err(|_| err(s))).transpose())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Arc::new(parsed.into_iter().collect::<PrimitiveArray<T>>()))
+    }
+
+    macro_rules! dictionary {
+        ($k:ty) => {{
+            let seed = StringArray::from(column.options.clone());
+            let mut builder = StringDictionaryBuilder::<$k>::new_with_dictionary(values.len(), &seed)
+                .map_err(|e| ZoonError::ParseError(e.to_string()))?;
+            for v in values {
+                match v {
+                    Some(s) => {
+                        builder.append(s).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+                    }
+                    None => builder.append_null(),
+                }
+            }
+            Ok(Arc::new(builder.finish()) as ArrayRef)
+        }};
+    }
+
+    match data_type {
+        DataType::Int8 => primitive::<Int8Type>(values, &parse_err),
+        DataType::Int16 => primitive::<Int16Type>(values, &parse_err),
+        DataType::Int32 => primitive::<Int32Type>(values, &parse_err),
+        DataType::Int64 => primitive::<Int64Type>(values, &parse_err),
+        DataType::UInt8 => primitive::<UInt8Type>(values, &parse_err),
+        DataType::UInt16 => primitive::<UInt16Type>(values, &parse_err),
+        DataType::UInt32 => primitive::<UInt32Type>(values, &parse_err),
+        DataType::UInt64 => primitive::<UInt64Type>(values, &parse_err),
+        DataType::Float32 => primitive::<Float32Type>(values, &parse_err),
+        DataType::Float64 => primitive::<Float64Type>(values, &parse_err),
+        DataType::Boolean => {
+            let mut builder = BooleanBuilder::with_capacity(values.len());
+            for v in values {
+                match v.as_deref() {
+                    Some("1" | "y" | "true") => builder.append_value(true),
+                    Some("0" | "n" | "false") => builder.append_value(false),
+                    Some(other) => return Err(parse_err(other)),
+                    None => builder.append_null(),
+                }
+            }
+            Ok(Arc::new(builder.finish()))
+        }
+        DataType::Utf8 => {
+            let mut builder = StringBuilder::with_capacity(values.len(), 0);
+            for v in values {
+                builder.append_option(v.as_deref());
+            }
+            Ok(Arc::new(builder.finish()))
+        }
+        DataType::LargeUtf8 => Ok(Arc::new(arrow_array::LargeStringArray::from(values.clone()))),
+        DataType::Dictionary(key, value) if **value == DataType::Utf8 => match **key {
+            DataType::Int8 => dictionary!(Int8Type),
+            DataType::Int16 => dictionary!(Int16Type),
+            DataType::Int32 => dictionary!(Int32Type),
+            DataType::Int64 => dictionary!(Int64Type),
+            DataType::UInt8 => dictionary!(UInt8Type),
+            DataType::UInt16 => dictionary!(UInt16Type),
+            DataType::UInt32 => dictionary!(UInt32Type),
+            DataType::UInt64 => dictionary!(UInt64Type),
+            _ => Err(ZoonError::UnsupportedType(format!("arrow type {}", data_type))),
+        },
+        other => Err(ZoonError::UnsupportedType(format!("arrow type {} in column {}", other, column.name))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::{BooleanArray, DictionaryArray, Float64Array, Int32Array, Int64Array};
+
+    fn sample_batch() -> RecordBatch {
+        let id = Int32Array::from(vec![1, 2, 3]);
+        let name = StringArray::from(vec!["Alice Smith", "Bob", "Carol"]);
+        let active = BooleanArray::from(vec![true, false, true]);
+        let role: DictionaryArray<Int32Type> = vec!["admin", "user", "user"].into_iter().collect();
+        let city = StringArray::from(vec![Some("Paris"), None, Some("Oslo")]);
+        let zip = Int64Array::from(vec![75001, 10001, 150]);
+        let address = StructArray::from(vec![
+            (Arc::new(Field::new("city", DataType::Utf8, true)), Arc::new(city) as ArrayRef),
+            (Arc::new(Field::new("zip", DataType::Int64, true)), Arc::new(zip) as ArrayRef),
+        ]);
+        RecordBatch::try_from_iter(vec![
+            ("id", Arc::new(id) as ArrayRef),
+            ("name", Arc::new(name) as ArrayRef),
+            ("active", Arc::new(active) as ArrayRef),
+            ("role", Arc::new(role) as ArrayRef),
+            ("address", Arc::new(address) as ArrayRef),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_record_batch_to_zoon() {
+        let zoon = record_batch_to_zoon(&sample_batch()).unwrap();
+        assert_eq!(
+            zoon,
+            "# id:i+ name:s active:b role=admin|user address.city:s address.zip:i\nAlice_Smith 1 admin Paris 75001\nBob 0 user ~ 10001\nCarol 1 user Oslo 150"
+        );
+
+        let decoded: Vec<serde_json::Value> = crate::decode(&zoon).unwrap();
+        assert_eq!(decoded[0]["address"]["city"], "Paris");
+        assert_eq!(decoded[2]["id"], 3);
+    }
+
+    #[test]
+    fn test_record_batch_roundtrip_with_schema() {
+        let batch = sample_batch();
+        let zoon = record_batch_to_zoon(&batch).unwrap();
+        let decoded = zoon_to_record_batch_with_schema(&zoon, batch.schema()).unwrap();
+        assert_eq!(decoded, batch);
+    }
+
+    #[test]
+    fn test_zoon_to_record_batch_inferred() {
+        let input = "%a=address\n# @region=eu id:i+ score:s level!low|medium|high %a.city:s\n0.5 0 Paris\n1.25 2 ~";
+        let batch = zoon_to_record_batch(input).unwrap();
+        assert_eq!(batch.num_rows(), 2);
+
+        let region = batch.column_by_name("region").unwrap().as_string::<i32>();
+        assert_eq!(region.value(1), "eu");
+        let id = batch.column_by_name("id").unwrap().as_primitive::<Int64Type>();
+        assert_eq!(id.values(), &[1, 2]);
+        let level = batch.column_by_name("level").unwrap().as_dictionary::<Int32Type>();
+        assert_eq!(level.keys().values(), &[0, 2]);
+        let address = batch.column_by_name("address").unwrap().as_struct();
+        let city = address.column_by_name("city").unwrap().as_string::<i32>();
+        assert!(city.is_null(1));
+
+        let schema = Arc::new(Schema::new(vec![Field::new("score", DataType::Float64, true)]));
+        let typed = zoon_to_record_batch_with_schema(input, schema).unwrap();
+        let score = typed.column(0).as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(score.value(1), 1.25);
+    }
+
+    #[test]
+    fn test_sequence_and_delta_columns() {
+        let ts = Int64Array::from(vec![1767225600, 1767225660, 1767225725, 1767225781]);
+        let slot = Int32Array::from(vec![900, 915, 930, 945]);
+        let batch =
+            RecordBatch::try_from_iter(vec![("ts", Arc::new(ts) as ArrayRef), ("slot", Arc::new(slot) as ArrayRef)]).unwrap();
+        let zoon = record_batch_to_zoon(&batch).unwrap();
+        assert_eq!(zoon, "# ts:i^ slot:i+900,15\n1767225600\n60\n65\n56");
+        let decoded = zoon_to_record_batch_with_schema(&zoon, batch.schema()).unwrap();
+        assert_eq!(decoded, batch);
+    }
+
+    #[test]
+    fn test_strings_that_need_quotes() {
+        let texts = vec![Some("\"quoted"), Some("[x]"), Some("#"), Some("~"), Some("a_b"), Some(""), None];
+        let text = StringArray::from(texts.clone());
+        let label: DictionaryArray<Int32Type> = vec!["a|b", "~", "c", "a|b", "_", "c", "*"].into_iter().collect();
+        let batch = RecordBatch::try_from_iter(vec![("text", Arc::new(text) as ArrayRef), ("label", Arc::new(label) as ArrayRef)])
+            .unwrap();
+        let zoon = record_batch_to_zoon(&batch).unwrap();
+        assert!(zoon.starts_with("# text:t label:t\n"));
+
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("text", DataType::Utf8, true),
+            Field::new("label", DataType::Utf8, true),
+        ]));
+        let decoded = zoon_to_record_batch_with_schema(&zoon, schema).unwrap();
+        let text = decoded.column(0).as_string::<i32>();
+        assert_eq!(text.iter().collect::<Vec<_>>(), texts);
+        let label = decoded.column(1).as_string::<i32>();
+        assert_eq!(label.iter().flatten().collect::<Vec<_>>(), ["a|b", "~", "c", "a|b", "_", "c", "*"]);
+    }
+
+    #[test]
+    fn test_column_clashing_with_struct() {
+        let err = zoon_to_record_batch("# a:i a.b:i\n1 2").unwrap_err();
+        assert!(matches!(err, ZoonError::UnsupportedType(_)));
+    }
+
+    #[test]
+    fn test_unsupported_arrow_type() {
+        let dates = arrow_array::Date32Array::from(vec![1, 2]);
+        let batch = RecordBatch::try_from_iter(vec![("day", Arc::new(dates) as ArrayRef)]).unwrap();
+        assert!(matches!(record_batch_to_zoon(&batch), Err(ZoonError::UnsupportedType(_))));
+    }
+}
@@ -0,0 +1,253 @@
+//! axum integration (feature `axum`).
+//!
+//! [`Zoon<T>`] extracts a request body sent as `text/zoon` or
+//! `application/vnd.zoon` (SPEC §15) and turns a value into a `text/zoon`
+//! response. [`ResponseFormat`] reads the `Accept` header for handlers that
+//! also answer JSON clients.
+
+use crate::ZoonError;
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{FromRequest, FromRequestParts, Request};
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Media type used for ZOON responses.
+pub const TEXT_ZOON: &str = "text/zoon";
+/// Alternative media type accepted for request bodies.
+pub const APPLICATION_ZOON: &str = "application/vnd.zoon";
+
+/// ZOON request body extractor and response.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Zoon<T>(pub T);
+
+/// Why a ZOON request was rejected.
+#[derive(Debug)]
+pub enum ZoonRejection {
+    /// `Content-Type` is missing, not a ZOON media type, or not UTF-8.
+    UnsupportedMediaType,
+    /// The `Accept` header rules out both ZOON and JSON.
+    NotAcceptable,
+    /// The body could not be read.
+    Body(BytesRejection),
+    /// The body is not valid ZOON for the target type.
+    Decode(ZoonError),
+}
+
+impl ZoonRejection {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            ZoonRejection::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ZoonRejection::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            ZoonRejection::Body(rejection) => rejection.status(),
+            ZoonRejection::Decode(ZoonError::LimitExceeded(_)) => StatusCode::PAYLOAD_TOO_LARGE,
+            ZoonRejection::Decode(ZoonError::UnsupportedType(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            ZoonRejection::Decode(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl std::fmt::Display for ZoonRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZoonRejection::UnsupportedMediaType => {
+                write!(f, "expected request with `Content-Type: {}` or `{}`", TEXT_ZOON, APPLICATION_ZOON)
+            }
+            ZoonRejection::NotAcceptable => write!(f, "expected `Accept` to allow {} or application/json", TEXT_ZOON),
+            ZoonRejection::Body(rejection) => write!(f, "{}", rejection.body_text()),
+            ZoonRejection::Decode(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ZoonRejection {}
+
+impl IntoResponse for ZoonRejection {
+    fn into_response(self) -> Response {
+        (self.status(), self.to_string()).into_response()
+    }
+}
+
+impl<T, S> FromRequest<S> for Zoon<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = ZoonRejection;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !has_zoon_content_type(req.headers()) {
+            return Err(ZoonRejection::UnsupportedMediaType);
+        }
+        let bytes = Bytes::from_request(req, state).await.map_err(ZoonRejection::Body)?;
+        let body = std::str::from_utf8(&bytes)
+            .map_err(|e| ZoonRejection::Decode(ZoonError::ParseError(e.to_string())))?;
+        crate::decode(body).map(Zoon).map_err(ZoonRejection::Decode)
+    }
+}
+
+impl<T: Serialize> IntoResponse for Zoon<T> {
+    fn into_response(self) -> Response {
+        match crate::encode(&self.0) {
+            Ok(body) => ([(CONTENT_TYPE, HeaderValue::from_static("text/zoon; charset=utf-8"))], body).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
+
+/// Response format chosen from the request's `Accept` header.
+///
+/// ZOON wins ties and is used when the header is absent; a header that
+/// allows neither ZOON nor JSON is rejected with 406.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Zoon,
+    Json,
+}
+
+impl ResponseFormat {
+    /// Picks the format with the highest `q` value in an `Accept` header.
+    pub fn negotiate(accept: &str) -> Option<ResponseFormat> {
+        let mut best: Option<(f32, ResponseFormat)> = None;
+        for range in accept.split(',') {
+            let mut params = range.split(';');
+            let media = params.next().unwrap_or_default().trim().to_ascii_lowercase();
+            let format = match media.as_str() {
+                TEXT_ZOON | APPLICATION_ZOON | "text/*" | "*/*" => ResponseFormat::Zoon,
+                "application/json" | "application/*" => ResponseFormat::Json,
+                _ => continue,
+            };
+            let q = params
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+            let better = best.is_none_or(|(best_q, _)| q > best_q || (q == best_q && format == ResponseFormat::Zoon));
+            if q > 0.0 && better {
+                best = Some((q, format));
+            }
+        }
+        best.map(|(_, format)| format)
+    }
+
+    /// Serializes `value` in this format.
+    pub fn respond<T: Serialize>(self, value: T) -> Response {
+        match self {
+            ResponseFormat::Zoon => Zoon(value).into_response(),
+            ResponseFormat::Json => match serde_json::to_string(&value) {
+                Ok(body) => ([(CONTENT_TYPE, HeaderValue::from_static("application/json"))], body).into_response(),
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+            },
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for ResponseFormat {
+    type Rejection = ZoonRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(accept) = parts.headers.get(ACCEPT) else {
+            return Ok(ResponseFormat::Zoon);
+        };
+        let accept = accept.to_str().map_err(|_| ZoonRejection::NotAcceptable)?;
+        ResponseFormat::negotiate(accept).ok_or(ZoonRejection::NotAcceptable)
+    }
+}
+
+fn has_zoon_content_type(headers: &HeaderMap) -> bool {
+    let Some(value) = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let mut params = value.split(';');
+    let media = params.next().unwrap_or_default().trim();
+    if !media.eq_ignore_ascii_case(TEXT_ZOON) && !media.eq_ignore_ascii_case(APPLICATION_ZOON) {
+        return false;
+    }
+    // ZOON is always UTF-8 (SPEC §15).
+    params.all(|param| match param.split_once('=') {
+        Some((name, charset)) if name.trim().eq_ignore_ascii_case("charset") => {
+            charset.trim().trim_matches('"').eq_ignore_ascii_case("utf-8")
+        }
+        _ => true,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        id: i64,
+        name: String,
+    }
+
+    fn request(content_type: Option<&str>, body: &str) -> Request {
+        let mut builder = Request::builder().method("POST").uri("/users");
+        if let Some(content_type) = content_type {
+            builder = builder.header(CONTENT_TYPE, content_type);
+        }
+        builder.body(Body::from(body.to_string())).unwrap()
+    }
+
+    async fn body_text(response: Response) -> String {
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(bytes.to_vec()).unwrap()
+    }
+
+    #[::tokio::test]
+    async fn test_extract() {
+        let body = "# id:i name:s\n1 Alice\n2 Bob_Smith";
+        for content_type in ["text/zoon", "application/vnd.zoon", "Text/ZOON; charset=UTF-8"] {
+            let Zoon(users) = Zoon::<Vec<User>>::from_request(request(Some(content_type), body), &()).await.unwrap();
+            assert_eq!(users[1], User { id: 2, name: "Bob Smith".into() });
+        }
+    }
+
+    #[::tokio::test]
+    async fn test_rejections() {
+        let body = "# id:i name:s\n1 Alice";
+        for content_type in [None, Some("application/json"), Some("text/zoon; charset=latin1")] {
+            let err = Zoon::<Vec<User>>::from_request(request(content_type, body), &()).await.unwrap_err();
+            assert_eq!(err.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+
+        let err = Zoon::<Vec<User>>::from_request(request(Some(TEXT_ZOON), "id:1"), &()).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::BAD_REQUEST);
+
+        let deep = format!("{}1{}", "a:{".repeat(100), "}".repeat(100));
+        let err = Zoon::<serde_json::Value>::from_request(request(Some(TEXT_ZOON), &deep), &()).await.unwrap_err();
+        assert_eq!(err.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let response = err.into_response();
+        assert!(body_text(response).await.starts_with("limit exceeded"));
+    }
+
+    #[::tokio::test]
+    async fn test_responses() {
+        let users = vec![User { id: 1, name: "Alice".into() }, User { id: 2, name: "Bob".into() }];
+        let response = Zoon(&users).into_response();
+        assert_eq!(response.headers()[CONTENT_TYPE], "text/zoon; charset=utf-8");
+        assert_eq!(body_text(response).await, "# id:i+ name:s\nAlice\nBob");
+
+        let response = ResponseFormat::Json.respond(&users);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(body_text(response).await, r#"[{"id":1,"name":"Alice"},{"id":2,"name":"Bob"}]"#);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(ResponseFormat::negotiate("text/zoon"), Some(ResponseFormat::Zoon));
+        assert_eq!(ResponseFormat::negotiate("application/json"), Some(ResponseFormat::Json));
+        assert_eq!(ResponseFormat::negotiate("application/json, text/zoon"), Some(ResponseFormat::Zoon));
+        assert_eq!(ResponseFormat::negotiate("text/zoon;q=0.5, application/json"), Some(ResponseFormat::Json));
+        assert_eq!(ResponseFormat::negotiate("*/*"), Some(ResponseFormat::Zoon));
+        assert_eq!(ResponseFormat::negotiate("text/zoon;q=0, application/json;q=0.1"), Some(ResponseFormat::Json));
+        assert_eq!(ResponseFormat::negotiate("image/png"), None);
+    }
+}
@@ -0,0 +1,248 @@
+//! Byte-pair encoding token counts from a local rank file.
+//!
+//! Rank files use the tiktoken layout (`cl100k_base.tiktoken`,
+//! `o200k_base.tiktoken`): one `<base64 token> <rank>` pair per line. Nothing is
+//! downloaded; point [`Vocabulary::from_file`] at a copy on disk.
+
+use crate::{Result, ZoonError};
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+/// A BPE vocabulary mapping byte sequences to merge ranks.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    ranks: HashMap<Vec<u8>, u32>,
+}
+
+impl Vocabulary {
+    /// Loads a tiktoken-style rank file.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Vocabulary> {
+        let file = std::fs::File::open(path).map_err(|e| ZoonError::Io(e.to_string()))?;
+        Vocabulary::from_reader(std::io::BufReader::new(file))
+    }
+
+    /// Reads `<base64 token> <rank>` lines.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Vocabulary> {
+        let mut ranks = HashMap::new();
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| ZoonError::Io(e.to_string()))?;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let invalid = || ZoonError::ParseError(format!("invalid rank file line {}", idx + 1));
+            let (token, rank) = line.split_once(' ').ok_or_else(invalid)?;
+            let token = decode_base64(token).ok_or_else(invalid)?;
+            let rank = rank.trim().parse::<u32>().map_err(|_| invalid())?;
+            ranks.insert(token, rank);
+        }
+        Ok(Vocabulary { ranks })
+    }
+
+    /// Number of entries in the vocabulary.
+    pub fn len(&self) -> usize {
+        self.ranks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranks.is_empty()
+    }
+
+    /// Counts the tokens `text` encodes to.
+    pub fn count(&self, text: &str) -> usize {
+        pre_tokenize(text).iter().map(|piece| self.count_piece(piece.as_bytes())).sum()
+    }
+
+    fn count_piece(&self, piece: &[u8]) -> usize {
+        if piece.len() <= 1 || self.ranks.contains_key(piece) {
+            return 1;
+        }
+
+        // Boundaries of the current parts; merge the lowest-ranked pair until
+        // no adjacent pair is in the vocabulary.
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        loop {
+            let mut best: Option<(u32, usize)> = None;
+            for i in 0..bounds.len() - 2 {
+                if let Some(&rank) = self.ranks.get(&piece[bounds[i]..bounds[i + 2]]) {
+                    if best.is_none_or(|(r, _)| rank < r) {
+                        best = Some((rank, i));
+                    }
+                }
+            }
+            match best {
+                Some((_, i)) => {
+                    bounds.remove(i + 1);
+                }
+                None => return bounds.len() - 1,
+            }
+        }
+    }
+}
+
+impl crate::TokenCost for Vocabulary {
+    fn cost(&self, text: &str) -> usize {
+        self.count(text)
+    }
+}
+
+/// Splits text the way the cl100k pattern does before merging:
+/// contractions, letter runs with one leading symbol, up to three digits,
+/// punctuation runs, and whitespace (a single space is kept with the
+/// following word).
+fn pre_tokenize(text: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let offset = |i: usize| chars.get(i).map_or(text.len(), |&(o, _)| o);
+    let is_letter = |i: usize| chars.get(i).is_some_and(|&(_, c)| c.is_alphabetic());
+    let is_digit = |i: usize| chars.get(i).is_some_and(|&(_, c)| c.is_numeric());
+    let is_space = |i: usize| chars.get(i).is_some_and(|&(_, c)| c.is_whitespace());
+    let is_newline = |i: usize| chars.get(i).is_some_and(|&(_, c)| c == '\r' || c == '\n');
+    let is_symbol = |i: usize| i < chars.len() && !is_letter(i) && !is_digit(i) && !is_space(i);
+
+    let mut pieces = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i].1;
+
+        if c == '\'' {
+            let rest: String = text[offset(i + 1)..].chars().take(2).collect::<String>().to_lowercase();
+            let len = ["s", "t", "re", "ve", "m", "ll", "d"]
+                .iter()
+                .find(|suffix| rest.starts_with(*suffix))
+                .map(|suffix| suffix.len());
+            if let Some(len) = len {
+                i += 1 + len;
+                pieces.push(&text[offset(start)..offset(i)]);
+                continue;
+            }
+        }
+
+        if is_letter(i) || (!is_newline(i) && !is_digit(i) && is_letter(i + 1)) {
+            i += 1;
+            while is_letter(i) {
+                i += 1;
+            }
+        } else if is_digit(i) {
+            while is_digit(i) && i - start < 3 {
+                i += 1;
+            }
+        } else if is_symbol(i) || (c == ' ' && is_symbol(i + 1)) {
+            i += 1;
+            while is_symbol(i) {
+                i += 1;
+            }
+            while is_newline(i) {
+                i += 1;
+            }
+        } else {
+            let mut end = i;
+            while is_space(end) {
+                end += 1;
+            }
+            if let Some(last_newline) = (i..end).rev().find(|&j| is_newline(j)) {
+                i = last_newline + 1;
+            } else if end < chars.len() && end - i > 1 {
+                // Leave the last space for the following word.
+                i = end - 1;
+            } else {
+                i = end;
+            }
+        }
+
+        pieces.push(&text[offset(start)..offset(i)]);
+    }
+    pieces
+}
+
+fn decode_base64(s: &str) -> Option<Vec<u8>> {
+    fn value(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    }
+
+    let bytes = s.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(bytes.len() * 3 / 4);
+    for chunk in bytes.chunks(4) {
+        let mut acc = 0u32;
+        for &c in chunk {
+            acc = (acc << 6) | value(c)?;
+        }
+        match chunk.len() {
+            4 => out.extend_from_slice(&[(acc >> 16) as u8, (acc >> 8) as u8, acc as u8]),
+            3 => out.extend_from_slice(&[(acc >> 10) as u8, (acc >> 2) as u8]),
+            2 => out.push((acc >> 4) as u8),
+            _ => return None,
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    fn encode_base64(bytes: &[u8]) -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let mut out = String::new();
+        for chunk in bytes.chunks(3) {
+            let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+            let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+            for i in 0..4 {
+                if i <= chunk.len() {
+                    out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+                } else {
+                    out.push('=');
+                }
+            }
+        }
+        out
+    }
+
+    /// All single bytes plus the given merges, in rank order.
+    pub(crate) fn test_vocabulary(merges: &[&str]) -> Vocabulary {
+        let mut lines = Vec::new();
+        for b in 0..=255u8 {
+            lines.push(format!("{} {}", encode_base64(&[b]), b));
+        }
+        for (i, merge) in merges.iter().enumerate() {
+            lines.push(format!("{} {}", encode_base64(merge.as_bytes()), 256 + i));
+        }
+        Vocabulary::from_reader(lines.join("\n").as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_pre_tokenize() {
+        assert_eq!(pre_tokenize("Hello world's 12345!!\n\n  x"), vec!["Hello", " world", "'s", " ", "123", "45", "!!\n\n", " ", " x"]);
+        assert_eq!(pre_tokenize("# id:i+ name:s"), vec!["#", " id", ":i", "+", " name", ":s"]);
+    }
+
+    #[test]
+    fn test_count() {
+        let vocab = test_vocabulary(&["ll", "he", "hell", "hello", " w", "or", " wor", "ld", " world"]);
+        assert_eq!(vocab.len(), 265);
+        assert_eq!(vocab.count("hello world"), 2);
+        assert_eq!(vocab.count("hellx"), 2);
+        assert_eq!(vocab.count("xyz"), 3);
+        assert_eq!(vocab.count(""), 0);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("zoon-bpe-{}.tiktoken", std::process::id()));
+        std::fs::write(&path, "aGk= 0\nIQ== 1\n").unwrap();
+        let vocab = Vocabulary::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(vocab.count("hi!"), 2);
+
+        let err = Vocabulary::from_reader("not-base64 x".as_bytes()).unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(_)));
+    }
+}
@@ -0,0 +1,72 @@
+//! Serde helpers for `chrono` types (feature `chrono`).
+//!
+//! `NaiveDate`, `NaiveDateTime` and `DateTime` already serialize as
+//! ISO 8601 text, which the encoder writes as `d` and `dt` columns.
+//! `TimeDelta` has no serde form; [`duration`] gives it the ISO 8601 text
+//! of a `du` column:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Job {
+//!     started: chrono::DateTime<chrono::Utc>,
+//!     #[serde(with = "zoon::chrono::duration")]
+//!     took: chrono::TimeDelta,
+//! }
+//! ```
+
+/// `TimeDelta` as a whole-second ISO 8601 duration such as `PT1H30M`.
+pub mod duration {
+    use crate::temporal::{format_duration, parse_duration};
+    use ::chrono::TimeDelta;
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(delta: &TimeDelta, serializer: S) -> Result<S::Ok, S::Error> {
+        if delta.subsec_nanos() != 0 {
+            return Err(S::Error::custom(format!("{} is not a whole number of seconds", delta)));
+        }
+        serializer.serialize_str(&format_duration(delta.num_seconds()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<TimeDelta, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        parse_duration(&text)
+            .and_then(TimeDelta::try_seconds)
+            .ok_or_else(|| D::Error::custom(format!("invalid duration {}", text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use ::chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Job {
+        day: NaiveDate,
+        started: DateTime<Utc>,
+        #[serde(with = "super::duration")]
+        took: TimeDelta,
+    }
+
+    #[test]
+    fn test_chrono_columns() {
+        let jobs: Vec<Job> = [(0, 5400), (1, 90), (3, 86400 + 7200)]
+            .into_iter()
+            .map(|(day, took)| Job {
+                day: NaiveDate::from_ymd_opt(2025, 12, 28 + day).unwrap(),
+                started: DateTime::from_timestamp(1766917667 + day as i64 * 3600, 0).unwrap(),
+                took: TimeDelta::seconds(took),
+            })
+            .collect();
+        let encoded = encode(&jobs).unwrap();
+        assert!(encoded.starts_with("# day:d2025-12-28 started:dt2025-12-28T10:27:47Z took:du\n0 0 5400\n1 3600 90\n"));
+        let decoded: Vec<Job> = decode(&encoded).unwrap();
+        assert_eq!(decoded, jobs);
+
+        let fractional = Job { took: TimeDelta::milliseconds(1500), ..decoded.into_iter().next().unwrap() };
+        assert!(encode(&[fractional]).is_err());
+    }
+}
@@ -1,9 +1,85 @@
+use crate::metadata::{is_comment_line, skip_comments};
+use crate::{ident, temporal};
 use crate::{Result, ZoonError};
 use serde::de::DeserializeOwned;
-use std::collections::{HashMap, BTreeMap};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Limits applied while decoding, so untrusted input cannot exhaust memory
+/// or the stack. Exceeding one fails with `ZoonError::LimitExceeded`.
+#[derive(Debug, Clone)]
+pub struct DecodeOptions {
+    /// Maximum nesting of `{...}` objects and dotted key segments.
+    pub max_depth: usize,
+    /// Maximum number of rows across all tables, `+N` rows included.
+    pub max_rows: usize,
+    /// Maximum row count declared by a single `+N`.
+    pub max_explicit_rows: usize,
+    /// Maximum length of a single line, in bytes.
+    pub max_line_length: usize,
+    /// Maximum size of the whole input, in bytes.
+    pub max_total_bytes: usize,
+    /// Maximum number of fields and constants in one header.
+    pub max_fields: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            max_depth: 64,
+            max_rows: 1_000_000,
+            max_explicit_rows: 1_000_000,
+            max_line_length: 1 << 20,
+            max_total_bytes: 64 << 20,
+            max_fields: 1024,
+        }
+    }
+}
+
+impl DecodeOptions {
+    /// Default limits without the row and input size caps, for streaming
+    /// decoders that do not keep rows in memory.
+    pub(crate) fn streaming() -> Self {
+        DecodeOptions {
+            max_rows: usize::MAX,
+            max_total_bytes: usize::MAX,
+            ..DecodeOptions::default()
+        }
+    }
+
+    fn check_line(&self, line: &str) -> Result<()> {
+        if line.len() > self.max_line_length {
+            return Err(ZoonError::LimitExceeded(format!(
+                "line of {} bytes exceeds max_line_length {}",
+                line.len(),
+                self.max_line_length
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, depth: usize, key: &str) -> Result<()> {
+        if depth > self.max_depth {
+            return Err(ZoonError::LimitExceeded(format!("{} exceeds max_depth {}", key, self.max_depth)));
+        }
+        Ok(())
+    }
+}
 
 pub fn decode<T: DeserializeOwned>(input: &str) -> Result<T> {
-    let input = input.trim();
+    decode_with_options(input, &DecodeOptions::default())
+}
+
+pub fn decode_with_options<T: DeserializeOwned>(input: &str, options: &DecodeOptions) -> Result<T> {
+    if input.len() > options.max_total_bytes {
+        return Err(ZoonError::LimitExceeded(format!(
+            "input of {} bytes exceeds max_total_bytes {}",
+            input.len(),
+            options.max_total_bytes
+        )));
+    }
+    let input = skip_comments(input).trim_end();
     if input.is_empty() {
         let empty_json = if std::any::type_name::<T>().contains("Vec") {
             "[]"
@@ -13,86 +89,568 @@ pub fn decode<T: DeserializeOwned>(input: &str) -> Result<T> {
         return serde_json::from_str(empty_json).map_err(|e| ZoonError::ParseError(e.to_string()));
     }
 
-    let json_value = if input.starts_with('#') || input.starts_with('%') {
-        decode_tabular(input)?
+    let json_value = if input.lines().next().and_then(section_name).is_some() {
+        decode_sections(input, options)?
     } else {
-        decode_inline(input)?
+        let (mut value, refs) = decode_value(input, options)?;
+        // Without sections there is no table to point into.
+        resolve_refs(&mut value, &refs, &serde_json::Map::new(), &mut HashMap::new(), &mut 0)?;
+        value
     };
 
     serde_json::from_value(json_value).map_err(|e| ZoonError::ParseError(e.to_string()))
 }
 
-fn decode_tabular(input: &str) -> Result<serde_json::Value> {
-    let mut lines = input.lines();
+fn decode_value(input: &str, options: &DecodeOptions) -> Result<(serde_json::Value, Vec<RefSpan>)> {
+    if is_tabular(input) {
+        decode_tabular(input, options)
+    } else {
+        for line in input.lines() {
+            options.check_line(line)?;
+        }
+        Ok((decode_inline_document(input, options)?, Vec::new()))
+    }
+}
+
+/// Whether a document is tabular. Inline documents may start with alias
+/// lines too; those are told apart by the first line after them opening
+/// with an inline entry rather than a header, dictionary line or row.
+/// Dictionary lines (`$ entry ...`) lead to a header, while `$a=x` is an
+/// inline entry.
+pub(crate) fn is_tabular(input: &str) -> bool {
+    let mut lines = input
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !is_alias_line(line) && !is_comment_line(line));
+    match lines.next() {
+        Some(line) if line.starts_with('#') => true,
+        Some(line) if dictionary_line(line).is_some() => {
+            lines.find(|line| dictionary_line(line).is_none()).is_none_or(|line| line.starts_with('#'))
+        }
+        Some(line) if input.starts_with('%') => {
+            line.split_whitespace().next().is_none_or(|token| !token.contains(['=', ':']))
+        }
+        Some(_) => false,
+        None => input.starts_with('%'),
+    }
+}
+
+/// Whether a line defines aliases (`%a=prefix`), as opposed to an inline
+/// line starting with an aliased key (`%a.key=value`, `%a:{...}`).
+fn is_alias_line(line: &str) -> bool {
+    line.strip_prefix('%')
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|token| token.split_once('='))
+        .is_some_and(|(alias, _)| !alias.contains(['.', ':']))
+}
+
+/// Decodes an inline document, expanding aliased top-level keys through
+/// the alias lines before it.
+fn decode_inline_document(input: &str, options: &DecodeOptions) -> Result<serde_json::Value> {
     let mut aliases = HashMap::new();
-    let mut header_line = String::new();
-    
-    // Parse aliases until header
-    while let Some(line) = lines.next() {
-        let line = line.trim();
-        if line.is_empty() { continue; }
-        
-        if line.starts_with('%') {
-            for part in line.split_whitespace() {
-                if let Some(idx) = part.find('=') {
-                     let alias = &part[1..idx]; // skip %
-                     let prefix = &part[idx+1..];
-                     aliases.insert(alias.to_string(), prefix.to_string());
-                }
+    let mut body = 0;
+    for line in input.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if is_alias_line(trimmed) {
+            parse_alias_line(trimmed, &mut aliases);
+            if aliases.len() > options.max_fields {
+                return Err(ZoonError::LimitExceeded(format!("more than max_fields {} aliases", options.max_fields)));
             }
-        } else if line.starts_with('#') {
-            header_line = line.to_string();
+        } else if !trimmed.is_empty() && !is_comment_line(trimmed) {
             break;
+        }
+        body += line.len();
+    }
+    decode_inline_with(&input[body..], options, 0, &aliases)
+}
+
+/// Name of a `## name` section line; the unnamed `##` section is `""`.
+fn section_name(line: &str) -> Option<&str> {
+    let line = line.trim();
+    if line == "##" {
+        return Some("");
+    }
+    let name = line.strip_prefix("## ")?.trim();
+    (!name.is_empty() && !name.contains(char::is_whitespace)).then_some(name)
+}
+
+/// Decodes a document of `## name` sections into an object keyed by name.
+/// Each section body is a table or an inline document; an empty body is an
+/// empty array. `max_rows` is shared by all sections.
+///
+/// Reference columns are resolved against the other sections. A document
+/// with an unnamed `##` section decodes to that section alone, the named
+/// ones being its lookup tables.
+fn decode_sections(input: &str, options: &DecodeOptions) -> Result<serde_json::Value> {
+    // (name, offset of the section line, offset of its body)
+    let mut sections = Vec::new();
+    let mut offset = 0;
+    for line in input.split_inclusive('\n') {
+        if let Some(name) = section_name(line) {
+            sections.push((name, offset, offset + line.len()));
+            if sections.len() > options.max_fields {
+                return Err(ZoonError::LimitExceeded(format!("more than max_fields {} sections", options.max_fields)));
+            }
+        }
+        offset += line.len();
+    }
+
+    let mut out = serde_json::Map::new();
+    let mut refs = Vec::new();
+    let mut rows = 0;
+    for (i, &(name, _, start)) in sections.iter().enumerate() {
+        let end = sections.get(i + 1).map_or(input.len(), |&(_, line_start, _)| line_start);
+        let body = skip_comments(&input[start..end]).trim_end();
+        let (value, spans) = if body.is_empty() {
+            (serde_json::Value::Array(Vec::new()), Vec::new())
         } else {
-             // Maybe implicit header not supported or error?
-             return Err(ZoonError::InvalidFormat("expected header starting with #".into()));
+            let section_options = DecodeOptions {
+                max_rows: options.max_rows.saturating_sub(rows),
+                ..options.clone()
+            };
+            decode_value(body, &section_options)?
+        };
+        if let serde_json::Value::Array(arr) = &value {
+            rows += arr.len();
+        }
+        if out.insert(name.to_string(), value).is_some() {
+            return Err(ZoonError::ParseError(format!("duplicate section {}", name)));
         }
+        refs.push((name, spans));
     }
-    
-    if header_line.is_empty() {
+
+    // Copies made while resolving count against the input size limit, so a
+    // small document cannot expand without bound.
+    let mut budget = options.max_total_bytes.saturating_sub(input.len());
+    let mut sizes = HashMap::new();
+    for (name, spans) in refs {
+        if spans.is_empty() {
+            continue;
+        }
+        // Taking the value leaves a null in place, so a section cannot
+        // point into itself and key order is kept.
+        let mut value = out[name].take();
+        resolve_refs(&mut value, &spans, &out, &mut sizes, &mut budget)?;
+        out[name] = value;
+    }
+
+    match out.get_mut("") {
+        Some(main) => Ok(main.take()),
+        None => Ok(serde_json::Value::Object(out)),
+    }
+}
+
+/// Reference columns of the header that applies from row `start` on.
+pub(crate) struct RefSpan {
+    start: usize,
+    /// Field name and the table it points into.
+    fields: Vec<(String, String)>,
+}
+
+/// Replaces reference indices in `value`'s rows with copies of the rows of
+/// the tables they point into.
+fn resolve_refs(
+    value: &mut serde_json::Value,
+    spans: &[RefSpan],
+    tables: &serde_json::Map<String, serde_json::Value>,
+    sizes: &mut HashMap<String, Vec<usize>>,
+    budget: &mut usize,
+) -> Result<()> {
+    let serde_json::Value::Array(rows) = value else {
+        return Ok(());
+    };
+    for (i, span) in spans.iter().enumerate() {
+        let end = spans.get(i + 1).map_or(rows.len(), |next| next.start).min(rows.len());
+        for (field, table) in &span.fields {
+            let Some(serde_json::Value::Array(targets)) = tables.get(table) else {
+                return Err(ZoonError::ParseError(format!("unknown table {} for {}", table, field)));
+            };
+            let sizes = sizes
+                .entry(table.clone())
+                .or_insert_with(|| targets.iter().map(|t| t.to_string().len()).collect());
+            let pointer = format!("/{}", field.replace('~', "~0").replace('/', "~1").replace('.', "/"));
+            for row in &mut rows[span.start.min(end)..end] {
+                let Some(cell) = row.pointer_mut(&pointer) else { continue };
+                if cell.is_null() {
+                    continue;
+                }
+                let idx = cell
+                    .as_u64()
+                    .and_then(|idx| usize::try_from(idx).ok())
+                    .filter(|&idx| idx < targets.len())
+                    .ok_or_else(|| ZoonError::ParseError(format!("invalid reference {} into {}", cell, table)))?;
+                *budget = budget.checked_sub(sizes[idx]).ok_or_else(|| {
+                    ZoonError::LimitExceeded("resolved references exceed max_total_bytes".into())
+                })?;
+                *cell = targets[idx].clone();
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "rayon"))]
+fn decode_tabular(input: &str, options: &DecodeOptions) -> Result<(serde_json::Value, Vec<RefSpan>)> {
+    let mut decoder = RowDecoder::restoring_order(options.clone());
+    let mut result = Vec::new();
+
+    for line in input.lines() {
+        decoder.push_line(line, &mut result)?;
+    }
+
+    if !decoder.has_header() {
+        return Err(ZoonError::InvalidFormat("missing header".into()));
+    }
+    if let Some(positions) = &decoder.positions {
+        restore_order(&mut result, positions)?;
+    }
+
+    Ok((serde_json::Value::Array(result), decoder.ref_spans))
+}
+
+/// Collects each run of body lines between headers and decodes it in
+/// parallel; headers and aliases still go through `push_line` in order.
+#[cfg(feature = "rayon")]
+fn decode_tabular(input: &str, options: &DecodeOptions) -> Result<(serde_json::Value, Vec<RefSpan>)> {
+    let mut decoder = RowDecoder::restoring_order(options.clone());
+    let mut result = Vec::new();
+    let mut body = Vec::new();
+
+    for line in input.lines() {
+        let trimmed = line.trim();
+        if decoder.has_header() && !trimmed.is_empty() && !is_header_line(trimmed) {
+            body.push(trimmed);
+            continue;
+        }
+        decoder.push_rows(&body, &mut result)?;
+        body.clear();
+        decoder.push_line(line, &mut result)?;
+    }
+    decoder.push_rows(&body, &mut result)?;
+
+    if !decoder.has_header() {
         return Err(ZoonError::InvalidFormat("missing header".into()));
     }
+    if let Some(positions) = &decoder.positions {
+        restore_order(&mut result, positions)?;
+    }
+
+    Ok((serde_json::Value::Array(result), decoder.ref_spans))
+}
+
+/// Puts rows back in input order from the cells of an order (`o`) column,
+/// which must number every row exactly once.
+fn restore_order(rows: &mut Vec<serde_json::Value>, positions: &[Option<u64>]) -> Result<()> {
+    if positions.is_empty() {
+        return Ok(());
+    }
+    let invalid = || ZoonError::ParseError("order column must number every row once".into());
+    if positions.len() != rows.len() {
+        return Err(invalid());
+    }
+    let mut slots: Vec<Option<serde_json::Value>> = (0..rows.len()).map(|_| None).collect();
+    for (row, position) in rows.drain(..).zip(positions) {
+        let slot = position
+            .and_then(|p| usize::try_from(p).ok())
+            .and_then(|p| slots.get_mut(p))
+            .ok_or_else(invalid)?;
+        if slot.replace(row).is_some() {
+            return Err(invalid());
+        }
+    }
+    // Every slot is filled: as many distinct positions as rows.
+    rows.extend(slots.into_iter().flatten());
+    Ok(())
+}
+
+/// Incremental tabular decoder: feed it lines, get rows back.
+///
+/// Alias and dictionary lines are collected until the header is seen. Once
+/// in the body, a line that is itself a header (`#` or `# ...`) replaces the
+/// schema for the rows that follow it.
+pub(crate) struct RowDecoder {
+    options: DecodeOptions,
+    aliases: HashMap<String, String>,
+    /// Entries of the `$` lines, shared by every `$` column.
+    dictionary: Arc<Vec<String>>,
+    header: Option<Header>,
+    /// Rows decoded under the current header, for `i+` values.
+    index: i64,
+    /// JSON pointer and running total of each delta (`i^`) column of the
+    /// current header.
+    deltas: Vec<(String, i64)>,
+    rows: usize,
+    /// Where reference columns start and stop, for resolving them once all
+    /// sections are decoded.
+    ref_spans: Vec<RefSpan>,
+    /// JSON pointer to the order (`o`) column of the current header.
+    order: Option<String>,
+    /// Order cell of each row since the first order column, when the
+    /// caller restores the order; streaming callers pass rows on as stored.
+    positions: Option<Vec<Option<u64>>>,
+}
 
-    let header_parts: Vec<&str> = header_line[2..].split_whitespace().collect();
+impl RowDecoder {
+    pub(crate) fn new(options: DecodeOptions) -> Self {
+        RowDecoder {
+            options,
+            aliases: HashMap::new(),
+            dictionary: Arc::default(),
+            header: None,
+            index: 0,
+            deltas: Vec::new(),
+            rows: 0,
+            ref_spans: Vec::new(),
+            order: None,
+            positions: None,
+        }
+    }
+
+    /// A decoder that keeps order (`o`) cells for [`restore_order`].
+    fn restoring_order(options: DecodeOptions) -> Self {
+        RowDecoder { positions: Some(Vec::new()), ..RowDecoder::new(options) }
+    }
+
+    fn count_rows(&mut self, n: usize) -> Result<()> {
+        self.rows = self.rows.saturating_add(n);
+        if self.rows > self.options.max_rows {
+            return Err(ZoonError::LimitExceeded(format!("more than max_rows {} rows", self.options.max_rows)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn has_header(&self) -> bool {
+        self.header.is_some()
+    }
+
+    /// Replaces the differences in delta columns with running totals.
+    fn accumulate(&mut self, row: &mut serde_json::Value) -> Result<()> {
+        for (pointer, total) in &mut self.deltas {
+            if let Some(cell) = row.pointer_mut(pointer) {
+                if let Some(delta) = cell.as_i64() {
+                    *total = total
+                        .checked_add(delta)
+                        .ok_or_else(|| ZoonError::ParseError(format!("delta overflow at {}", pointer)))?;
+                    *cell = serde_json::Value::from(*total);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes the order (`o`) cell out of a decoded row.
+    fn take_position(&mut self, row: &mut serde_json::Value) {
+        let Some(pointer) = &self.order else {
+            if let Some(positions) = self.positions.as_mut().filter(|p| !p.is_empty()) {
+                positions.push(None);
+            }
+            return;
+        };
+        let (parent, key) = pointer.rsplit_once('/').unwrap_or_default();
+        let key = key.replace("~1", "/").replace("~0", "~");
+        let cell = row.pointer_mut(parent).and_then(|p| p.as_object_mut()).and_then(|obj| obj.remove(&key));
+        if let Some(positions) = &mut self.positions {
+            positions.push(cell.and_then(|c| c.as_u64()));
+        }
+    }
+
+    /// Decodes trimmed body lines under the current header in parallel,
+    /// appending the rows in input order.
+    #[cfg(feature = "rayon")]
+    fn push_rows(&mut self, lines: &[&str], out: &mut Vec<serde_json::Value>) -> Result<()> {
+        use rayon::prelude::*;
+
+        if lines.is_empty() {
+            return Ok(());
+        }
+        for line in lines {
+            self.options.check_line(line)?;
+        }
+        self.count_rows(lines.len())?;
+        let Some(header) = &self.header else {
+            return Ok(());
+        };
+
+        let base = self.index;
+        let mut rows = lines
+            .par_iter()
+            .with_min_len(1024)
+            .enumerate()
+            .map(|(i, line)| header.row(line, base + i as i64))
+            .collect::<Result<Vec<_>>>()?;
+        self.index = base + lines.len() as i64;
+        // Running totals depend on every earlier row.
+        if !self.deltas.is_empty() || self.order.is_some() || self.positions.as_ref().is_some_and(|p| !p.is_empty()) {
+            for row in &mut rows {
+                self.accumulate(row)?;
+                self.take_position(row);
+            }
+        }
+        out.extend(rows);
+        Ok(())
+    }
+
+    pub(crate) fn push_line(&mut self, line: &str, out: &mut Vec<serde_json::Value>) -> Result<()> {
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+        self.options.check_line(line)?;
+
+        match &self.header {
+            None => {
+                if is_comment_line(line) {
+                    return Ok(());
+                } else if line.starts_with('%') {
+                    parse_alias_line(line, &mut self.aliases);
+                    if self.aliases.len() > self.options.max_fields {
+                        return Err(ZoonError::LimitExceeded(format!("more than max_fields {} aliases", self.options.max_fields)));
+                    }
+                    return Ok(());
+                } else if let Some(entries) = dictionary_line(line) {
+                    // Nothing holds the dictionary before the header, so
+                    // this never copies it.
+                    Arc::make_mut(&mut self.dictionary).extend(entries.map(String::from));
+                    return Ok(());
+                } else if !line.starts_with('#') {
+                    // Maybe implicit header not supported or error?
+                    return Err(ZoonError::InvalidFormat("expected header starting with #".into()));
+                }
+            }
+            Some(_) if !is_header_line(line) => {
+                self.count_rows(1)?;
+                if let Some(header) = &self.header {
+                    let mut row = header.row(line, self.index)?;
+                    self.index += 1;
+                    self.accumulate(&mut row)?;
+                    self.take_position(&mut row);
+                    out.push(row);
+                }
+                return Ok(());
+            }
+            Some(_) => {}
+        }
+
+        let header = parse_header(line, &self.aliases, &self.dictionary, &self.options)?;
+        let fields: Vec<(String, String)> = header
+            .fields
+            .iter()
+            .filter(|f| f.typ == "r")
+            .map(|f| (f.name.clone(), f.options[0].clone()))
+            .collect();
+        // A span without references is only needed to end the previous one.
+        if !fields.is_empty() || self.ref_spans.last().is_some_and(|s| !s.fields.is_empty()) {
+            self.ref_spans.push(RefSpan { start: self.rows, fields });
+        }
+        self.count_rows(header.explicit_rows)?;
+        self.index = 0;
+        self.deltas = header
+            .fields
+            .iter()
+            .filter(|f| f.typ == "i^")
+            .map(|f| (header.pointer(&f.name), 0))
+            .collect();
+        self.order = header.fields.iter().find(|f| f.typ == ORDER_TYPE).map(|f| header.pointer(&f.name));
+        for _ in 0..header.explicit_rows {
+            let mut row = header.row("", self.index)?;
+            self.index += 1;
+            self.take_position(&mut row);
+            out.push(row);
+        }
+        self.header = Some(header);
+        Ok(())
+    }
+}
+
+pub(crate) fn is_header_line(line: &str) -> bool {
+    line == "#" || line.starts_with("# ")
+}
+
+fn parse_alias_line(line: &str, aliases: &mut HashMap<String, String>) {
+    for part in line.split_whitespace() {
+        if let Some(idx) = part.find('=') {
+             let alias = part[..idx].trim_start_matches('%');
+             // A prefix may itself go through an earlier alias.
+             let prefix = unalias(&part[idx+1..], aliases);
+             aliases.insert(alias.to_string(), prefix);
+        }
+    }
+}
+
+/// Entries of a `$ entry entry ...` dictionary line.
+pub(crate) fn dictionary_line(line: &str) -> Option<std::str::SplitWhitespace<'_>> {
+    let entries = line.strip_prefix('$')?;
+    (entries.is_empty() || entries.starts_with(' ')).then(|| entries.split_whitespace())
+}
+
+/// Expands a leading `%alias` or `%alias.rest`; unknown aliases are kept.
+fn unalias(name: &str, aliases: &HashMap<String, String>) -> String {
+    let Some(aliased) = name.strip_prefix('%') else {
+        return name.to_string();
+    };
+    let (alias, rest) = match aliased.find('.') {
+        Some(dot) => (&aliased[..dot], &aliased[dot..]),
+        None => (aliased, ""),
+    };
+    match aliases.get(alias) {
+        Some(prefix) => format!("{}{}", prefix, rest),
+        None => name.to_string(),
+    }
+}
+
+pub(crate) struct Header {
+    pub(crate) fields: Vec<HeaderField>,
+    pub(crate) constants: Vec<ConstantField>,
+    pub(crate) explicit_rows: usize,
+    /// Whether any name is dotted, so rows need unflattening.
+    nested: bool,
+}
+
+fn parse_header(
+    header_line: &str,
+    aliases: &HashMap<String, String>,
+    dictionary: &Arc<Vec<String>>,
+    options: &DecodeOptions,
+) -> Result<Header> {
+    let header_parts: Vec<&str> = header_line.trim_start_matches('#').split_whitespace().collect();
     let mut fields: Vec<HeaderField> = Vec::new();
     let mut constants: Vec<ConstantField> = Vec::new();
-    let mut explicit_rows = -1;
+    let mut explicit_rows = 0;
 
     for part in header_parts {
-        if part.starts_with('+') {
-            if let Ok(n) = part[1..].parse::<i32>() {
-                explicit_rows = n;
+        if let Some(count) = part.strip_prefix('+') {
+            let exceeded = || ZoonError::LimitExceeded(format!("+{} exceeds max_explicit_rows {}", count, options.max_explicit_rows));
+            let n = match count.parse::<usize>() {
+                Ok(n) => n,
+                // Too many digits for a usize.
+                Err(_) if !count.is_empty() && count.bytes().all(|b| b.is_ascii_digit()) => return Err(exceeded()),
+                Err(_) => return Err(ZoonError::ParseError(format!("invalid row count +{}", count))),
+            };
+            if n > options.max_explicit_rows {
+                return Err(exceeded());
             }
+            explicit_rows = n;
             continue;
         }
+        if fields.len() + constants.len() >= options.max_fields {
+            return Err(ZoonError::LimitExceeded(format!("more than max_fields {} fields", options.max_fields)));
+        }
 
         let is_const = part.starts_with('@');
         let clean_part = if is_const { &part[1..] } else { part };
         
-        // Find separator : or =
-        let sep_idx = clean_part.find(|c| c == ':' || c == '=' || c == '!');
+        // Find separator : or =. A reference (`name>table`) has nothing
+        // after the table name, so `a>b:i` is an `i` column named `a>b`.
+        let sep_idx = clean_part.find([':', '=', '!']).or_else(|| clean_part.rfind('>'));
         if sep_idx.is_none() { continue; }
         let idx = sep_idx.unwrap();
         
         let raw_name = &clean_part[..idx];
         let suffix = &clean_part[idx+1..]; // includes type or value
-        let sep = clean_part.chars().nth(idx).unwrap();
+        let sep = clean_part[idx..].chars().next().unwrap_or(':');
         
-        // Unalias
-        let mut name = raw_name.to_string();
-        if name.starts_with('%') {
-             if let Some(dot_idx) = name.find('.') {
-                 let alias = &name[1..dot_idx];
-                 if let Some(prefix) = aliases.get(alias) {
-                     name = format!("{}.{}", prefix, &name[dot_idx+1..]);
-                 }
-             } else {
-                 let alias = &name[1..];
-                 if let Some(prefix) = aliases.get(alias) {
-                     name = prefix.clone();
-                 }
-             }
-        }
+        let name = unalias(raw_name, aliases);
+        options.check_depth(name.split('.').count(), &name)?;
         
         if is_const {
             let mut val_str = suffix.to_string();
@@ -101,131 +659,310 @@ fn decode_tabular(input: &str) -> Result<serde_json::Value> {
                 typ = "s".to_string();
                 val_str = val_str.replace('_', " ");
             } else {
-                // inferred type from suffix? 
-                // Currently suffix IS the value for constants usually?
                 // Syntax: @name=value (string) or @name:value (inferred)
                 typ = "auto".to_string();
             }
             
-            // Fix boolean constant display (y/n -> 1/0 or boolean)
-            if sep == ':' && (val_str == "y" || val_str == "n") {
-                 // handle in parse_value
-            }
-            
             constants.push(ConstantField {
+                value: parse_value(&val_str, &typ),
                 name,
                 val: val_str,
                 typ,
             });
         } else {
-            let typ = if sep == '=' { "s".to_string() } else { suffix.to_string() };
+            let (typ, options) = match sep {
+                '=' => ("s".to_string(), Arc::new(suffix.split('|').map(String::from).collect())),
+                '!' => ("e".to_string(), Arc::new(suffix.split('|').map(String::from).collect())),
+                '>' => ("r".to_string(), Arc::new(vec![suffix.to_string()])),
+                // Dictionary columns are indexed enums over the `$` lines.
+                _ if suffix == "$" => ("e".to_string(), Arc::clone(dictionary)),
+                _ => (suffix.to_string(), Arc::default()),
+            };
+            let (typ, default) = match typ.split_once('@') {
+                Some((typ, default)) => (typ.to_string(), Some(parse_value(default, typ))),
+                None => (typ, None),
+            };
+            let (typ, sequence) = match typ.strip_prefix("i+") {
+                Some(spec) => ("i+".to_string(), parse_sequence(spec, &name)?),
+                None => (typ, (1, 1)),
+            };
+            let (typ, base) = temporal::split_base(&typ, &name)?;
+            let (typ, prefix) = match typ.split_once('+') {
+                Some((typ @ ("s" | "t" | "u" | "x"), prefix)) => (typ.to_string(), prefix.to_string()),
+                _ => (typ, String::new()),
+            };
             fields.push(HeaderField {
                 name,
                 typ,
+                options,
+                sequence,
+                base,
+                prefix,
+                default,
             });
         }
     }
-    
-    let mut result = Vec::new();
-    let mut auto_inc = 0;
 
-    // Helper to process row values
-    let mut process_row = |values: Vec<String>| -> Result<serde_json::Value> {
-        let mut flat_obj = serde_json::Map::new();
-        
+    let nested = fields.iter().map(|f| &f.name).chain(constants.iter().map(|c| &c.name)).any(|n| n.contains('.'));
+    Ok(Header {
+        fields,
+        constants,
+        explicit_rows,
+        nested,
+    })
+}
+
+/// Start and step of an `i+` column: empty for `1,1`, else `start` or
+/// `start,step`.
+fn parse_sequence(spec: &str, name: &str) -> Result<(i64, i64)> {
+    if spec.is_empty() {
+        return Ok((1, 1));
+    }
+    let (start, step) = spec.split_once(',').unwrap_or((spec, "1"));
+    match (start.parse(), step.parse()) {
+        (Ok(start), Ok(step)) => Ok((start, step)),
+        _ => Err(ZoonError::ParseError(format!("invalid sequence i+{} for {}", spec, name))),
+    }
+}
+
+impl Header {
+    /// JSON pointer to a field in a decoded row.
+    fn pointer(&self, name: &str) -> String {
+        let escape = |part: &str| part.replace('~', "~0").replace('/', "~1");
+        if self.nested {
+            name.split('.').map(|part| format!("/{}", escape(part))).collect()
+        } else {
+            format!("/{}", escape(name))
+        }
+    }
+
+    /// Lines up row tokens with the header fields, filling in `i+` values
+    /// for the row at `index` and `~` for missing trailing values. Delta
+    /// (`i^`) tokens are left as differences.
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    pub(crate) fn split_row<'a>(&self, line: &'a str, index: i64) -> Result<Vec<Cow<'a, str>>> {
+        let mut tokens = tokenize_row(line);
+        self.fields
+            .iter()
+            .map(|field| {
+                if field.typ == "i+" {
+                    Ok(Cow::Owned(field.sequence_value(index)?.to_string()))
+                } else {
+                    Ok(Cow::Borrowed(tokens.next().transpose()?.unwrap_or("~")))
+                }
+            })
+            .collect()
+    }
+
+    /// Decodes the row at `index` under this header. Delta columns hold the
+    /// raw differences; [`RowDecoder`] adds them up.
+    fn row(&self, line: &str, index: i64) -> Result<serde_json::Value> {
+        let mut flat_obj = serde_json::Map::with_capacity(self.constants.len() + self.fields.len());
+
         // Apply constants
-        for c in &constants {
-            let val = parse_value(&c.val, &c.typ);
-            flat_obj.insert(c.name.clone(), val);
+        for c in &self.constants {
+            flat_obj.insert(c.name.clone(), c.value.clone());
         }
-        
-        let mut val_idx = 0;
-        for field in &fields {
-            let value_str = if field.typ == "i+" {
-                auto_inc += 1;
-                auto_inc.to_string()
+
+        let mut tokens = tokenize_row(line);
+        for field in &self.fields {
+            let json_val = if field.typ == "i+" {
+                serde_json::Value::from(field.sequence_value(index)?)
             } else {
-                 // If values missing?
-                 if val_idx >= values.len() {
-                     "~".to_string()
-                 } else {
-                     let v = values[val_idx].clone();
-                     val_idx += 1;
-                     v
-                 }
+                let token = tokens.next().transpose()?;
+                if let (Some(DEFAULT_CELL), Some(default)) = (token, &field.default) {
+                    // A default is the whole value, prefix included.
+                    flat_obj.insert(field.name.clone(), default.clone());
+                    continue;
+                }
+                match token {
+                    None | Some("~") => serde_json::Value::Null,
+                    Some(token) if token.starts_with('"') => serde_json::Value::String(quoted_text(token)),
+                    Some(token) if field.typ == "e" => parse_value(field.option(token)?, "s"),
+                    Some(token) if field.typ == "i^" => serde_json::Value::from(field.delta(token)?),
+                    Some(token) if matches!(field.typ.as_str(), "d" | "dt" | "du") => {
+                        serde_json::Value::String(field.temporal(token)?)
+                    }
+                    Some(token) if matches!(field.typ.as_str(), "u" | "x") => serde_json::Value::String(field.identifier(token)?),
+                    Some(token) => parse_value(token, &field.typ),
+                }
             };
-            
-            if value_str != "~" {
-                let json_val = parse_value(&value_str, &field.typ);
-                flat_obj.insert(field.name.clone(), json_val);
-            }
+            let json_val = match json_val {
+                serde_json::Value::String(s) if !field.prefix.is_empty() => serde_json::Value::String(field.prefix.clone() + &s),
+                other => other,
+            };
+            flat_obj.insert(field.name.clone(), json_val);
         }
-        
-        // Unflatten
-        unflatten_object(flat_obj)
-    };
 
-    if explicit_rows > 0 {
-        for _ in 0..explicit_rows {
-            result.push(process_row(Vec::new())?);
+        if self.nested {
+            unflatten_object(flat_obj)
+        } else {
+            Ok(serde_json::Value::Object(flat_obj))
         }
     }
+}
 
-    for line in lines {
-        let line = line.trim();
-        if line.is_empty() { continue; }
-        
-        let values = tokenize_row(line);
-        result.push(process_row(values)?);
+impl HeaderField {
+    /// Resolves an indexed enum token to its option.
+    pub(crate) fn option(&self, token: &str) -> Result<&str> {
+        token
+            .parse::<usize>()
+            .ok()
+            .and_then(|idx| self.options.get(idx))
+            .map(String::as_str)
+            .ok_or_else(|| ZoonError::ParseError(format!("invalid enum index {} for {}", token, self.name)))
     }
 
-    Ok(serde_json::Value::Array(result))
+    /// Value of an `i+` column in the row at `index`.
+    pub(crate) fn sequence_value(&self, index: i64) -> Result<i64> {
+        let (start, step) = self.sequence;
+        step.checked_mul(index)
+            .and_then(|offset| start.checked_add(offset))
+            .ok_or_else(|| ZoonError::ParseError(format!("sequence overflow in {}", self.name)))
+    }
+
+    /// Text of a `d`, `dt` or `du` cell: durations and offsets from a base
+    /// are whole numbers, other cells are the literal value.
+    pub(crate) fn temporal(&self, token: &str) -> Result<String> {
+        let number = || {
+            token
+                .parse::<i64>()
+                .map_err(|_| ZoonError::ParseError(format!("invalid offset {} for {}", token, self.name)))
+        };
+        let offset = |base: i64| {
+            base.checked_add(number()?)
+                .ok_or_else(|| ZoonError::ParseError(format!("offset overflow in {}", self.name)))
+        };
+        match (self.typ.as_str(), &self.base) {
+            ("du", _) => Ok(temporal::format_duration(number()?)),
+            ("d", Some((days, _))) => temporal::format_date(offset(*days)?),
+            ("dt", Some((seconds, zone))) => temporal::format_datetime(offset(*seconds)?, zone),
+            _ => Ok(token.to_string()),
+        }
+    }
+
+    /// Text of a compact `u` or `x` cell, without the prefix.
+    pub(crate) fn identifier(&self, token: &str) -> Result<String> {
+        ident::expand(token, &self.typ)
+            .ok_or_else(|| ZoonError::ParseError(format!("invalid {} identifier {} for {}", self.typ, token, self.name)))
+    }
+
+    /// Parses a delta (`i^`) token.
+    pub(crate) fn delta(&self, token: &str) -> Result<i64> {
+        token
+            .parse()
+            .map_err(|_| ZoonError::ParseError(format!("invalid delta {} for {}", token, self.name)))
+    }
 }
 
-fn tokenize_row(line: &str) -> Vec<String> {
-    let mut tokens = Vec::new();
-    let chars: Vec<char> = line.chars().collect();
-    let mut i = 0;
-    while i < chars.len() {
-        while i < chars.len() && chars[i] == ' ' {
-            i += 1;
+/// Reads alias and dictionary lines and the header of a single-table document, returning
+/// the header and the remaining body lines.
+pub(crate) fn read_header<'a>(input: &'a str, options: &DecodeOptions) -> Result<(Header, std::str::Lines<'a>)> {
+    let mut aliases = HashMap::new();
+    let mut dictionary = Arc::new(Vec::new());
+    let mut lines = input.lines();
+    for line in lines.by_ref() {
+        let line = line.trim();
+        if line.is_empty() || is_comment_line(line) {
+            continue;
         }
-        if i >= chars.len() {
-            break;
+        if line.starts_with('%') {
+            parse_alias_line(line, &mut aliases);
+        } else if let Some(entries) = dictionary_line(line) {
+            Arc::make_mut(&mut dictionary).extend(entries.map(String::from));
+        } else if line.starts_with('#') {
+            return Ok((parse_header(line, &aliases, &dictionary, options)?, lines));
+        } else {
+            return Err(ZoonError::InvalidFormat("expected header starting with #".into()));
         }
-        if chars[i] == '"' {
-            let mut end = i + 1;
-            while end < chars.len() {
-                if chars[end] == '\\' && end + 1 < chars.len() {
-                    end += 2;
-                } else if chars[end] == '"' {
-                    end += 1;
-                    break;
-                } else {
-                    end += 1;
+    }
+    Err(ZoonError::InvalidFormat("missing header".into()))
+}
+
+/// End of the `[...]` or `{...}` value opening at `start`, past its
+/// closing bracket. Brackets inside quoted items do not count.
+pub(crate) fn bracket_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut pos = start;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' if quoted => pos += 1,
+            b'"' => quoted = !quoted,
+            b'[' | b'{' if !quoted => depth += 1,
+            b']' | b'}' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(pos + 1);
                 }
             }
-            let content: String = chars[i+1..end-1].iter().collect();
-            tokens.push(content);
-            i = end;
-        } else if chars[i] == '[' {
-            let mut end = i + 1;
-            while end < chars.len() && chars[end] != ']' {
-                end += 1;
+            _ => {}
+        }
+        pos += 1;
+    }
+    None
+}
+
+/// Splits a row into tokens without copying it.
+pub(crate) fn tokenize_row(line: &str) -> RowTokens<'_> {
+    RowTokens { line, pos: 0 }
+}
+
+/// Iterator over the tokens of a row. Quoted tokens come back as written,
+/// quotes and escapes included; [`quoted_text`] reads them.
+pub(crate) struct RowTokens<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for RowTokens<'a> {
+    type Item = Result<&'a str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Every delimiter is ASCII, so byte offsets of matches are always
+        // char boundaries.
+        let bytes = self.line.as_bytes();
+        while self.pos < bytes.len() && bytes[self.pos] == b' ' {
+            self.pos += 1;
+        }
+        if self.pos >= bytes.len() {
+            return None;
+        }
+
+        let start = self.pos;
+        match bytes[start] {
+            b'"' => {
+                let mut end = start + 1;
+                while end < bytes.len() {
+                    match bytes[end] {
+                        b'\\' if end + 1 < bytes.len() => end += 2,
+                        b'"' => {
+                            self.pos = end + 1;
+                            return Some(Ok(&self.line[start..self.pos]));
+                        }
+                        _ => end += 1,
+                    }
+                }
+                self.pos = bytes.len();
+                Some(Err(ZoonError::ParseError(format!("unterminated string in row: {}", self.line))))
             }
-            let content: String = chars[i..end+1].iter().collect();
-            tokens.push(content);
-            i = end + 1;
-        } else {
-            let start = i;
-            while i < chars.len() && chars[i] != ' ' {
-                i += 1;
+            b'[' => match bracket_end(bytes, start) {
+                Some(end) => {
+                    self.pos = end;
+                    Some(Ok(&self.line[start..end]))
+                }
+                None => {
+                    self.pos = bytes.len();
+                    Some(Err(ZoonError::ParseError(format!("unterminated array in row: {}", self.line))))
+                }
+            },
+            _ => {
+                let len = bytes[start..].iter().position(|&b| b == b' ').unwrap_or(bytes.len() - start);
+                self.pos = start + len;
+                Some(Ok(&self.line[start..self.pos]))
             }
-            let content: String = chars[start..i].iter().collect();
-            tokens.push(content);
         }
     }
-    tokens
 }
 
 fn unflatten_object(flat: serde_json::Map<String, serde_json::Value>) -> Result<serde_json::Value> {
@@ -243,19 +980,15 @@ fn insert_nested(
     path: &str,
     value: serde_json::Value
 ) -> Result<()> {
-    let parts: Vec<&str> = path.split('.').collect();
-    if parts.len() == 1 {
-        obj.insert(parts[0].to_string(), value);
+    let Some((current_key, remaining_path)) = path.split_once('.') else {
+        obj.insert(path.to_string(), value);
         return Ok(());
-    }
-    
-    let current_key = parts[0];
-    let remaining_path = parts[1..].join(".");
-    
+    };
+
     let entry = obj.entry(current_key.to_string()).or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
     
     if let serde_json::Value::Object(inner_obj) = entry {
-        insert_nested(inner_obj, &remaining_path, value)?;
+        insert_nested(inner_obj, remaining_path, value)?;
     } else {
         // Conflict: trying to insert into non-object? 
         // Or mixed types.
@@ -266,96 +999,129 @@ fn insert_nested(
     Ok(())
 }
 
-fn decode_inline(input: &str) -> Result<serde_json::Value> {
+#[cfg_attr(not(any(feature = "yaml", feature = "toml")), allow(dead_code))]
+pub(crate) fn decode_inline(input: &str) -> Result<serde_json::Value> {
+    decode_inline_document(input, &DecodeOptions::default())
+}
+
+/// Decodes inline entries nested `depth` levels down; `aliases` apply to
+/// top-level keys.
+fn decode_inline_with(
+    input: &str,
+    options: &DecodeOptions,
+    depth: usize,
+    aliases: &HashMap<String, String>,
+) -> Result<serde_json::Value> {
     let mut obj = serde_json::Map::new();
     let mut pos = 0;
-    let chars: Vec<char> = input.chars().collect();
+    // Delimiters are ASCII, so slicing at their byte offsets is safe.
+    let bytes = input.as_bytes();
 
-    while pos < chars.len() {
-        while pos < chars.len() && chars[pos].is_whitespace() {
+    while pos < bytes.len() {
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
             pos += 1;
         }
-        if pos >= chars.len() {
+        if pos >= bytes.len() {
             break;
         }
 
         let key_start = pos;
-        while pos < chars.len() && chars[pos] != ':' && chars[pos] != '=' {
+        while pos < bytes.len() && bytes[pos] != b':' && bytes[pos] != b'=' {
             pos += 1;
         }
-        let mut key: String = chars[key_start..pos].iter().collect();
-        
-        if pos >= chars.len() {
+        let key = &input[key_start..pos];
+        let key = if depth == 0 { unalias(key, aliases) } else { key.to_string() };
+        let key = key.as_str();
+
+        if pos >= bytes.len() {
             break;
         }
 
-        let sep = chars[pos];
+        let sep = bytes[pos];
         pos += 1;
 
         let val_start = pos;
-        if pos < chars.len() && chars[pos] == '{' {
-            let mut depth = 1;
-            pos += 1;
-            while pos < chars.len() && depth > 0 {
-                if chars[pos] == '{' {
-                    depth += 1;
-                }
-                if chars[pos] == '}' {
-                    depth -= 1;
-                }
-                pos += 1;
-            }
+        if pos < bytes.len() && matches!(bytes[pos], b'{' | b'[') {
+            pos = bracket_end(bytes, pos).unwrap_or(bytes.len());
+        } else if pos < bytes.len() && sep == b'=' && bytes[pos] == b'"' {
+            pos = quoted_end(bytes, pos);
         } else {
-            while pos < chars.len() && !chars[pos].is_whitespace() {
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() {
                 pos += 1;
             }
         }
         
-        let value_str: String = chars[val_start..pos].iter().collect();
+        let value_str = &input[val_start..pos];
         
         let json_val = if value_str.starts_with('{') && value_str.ends_with('}') {
             let inner = &value_str[1..value_str.len() - 1];
-            decode_inline(inner)?
-        } else if sep == '=' {
+            let depth = depth + key.split('.').count();
+            options.check_depth(depth, key)?;
+            decode_inline_with(inner, options, depth, aliases)?
+        } else if sep == b'=' && value_str.starts_with('"') {
+            serde_json::Value::String(quoted_text(value_str))
+        } else if sep == b'=' {
             serde_json::Value::String(value_str.replace('_', " "))
         } else {
-            parse_value(&value_str, "auto")
+            parse_value(value_str, "auto")
         };
         
         // Handle nested keys in inline? e.g. server.host=...
         // Spec implies inline uses { } nesting usually, but key could be dot-notated?
         // Assuming dot-notation is allowed in inline keys too.
         if key.contains('.') {
+             options.check_depth(depth + key.split('.').count(), key)?;
              // unflatten handling
              // We can't use insert_nested easily on 'obj' while iterating?
              // Actually 'obj' is the map we are building.
-             insert_nested(&mut obj, &key, json_val)?;
+             insert_nested(&mut obj, key, json_val)?;
         } else {
-             obj.insert(key, json_val);
+             obj.insert(key.to_string(), json_val);
         }
     }
 
     Ok(serde_json::Value::Object(obj))
 }
 
-struct HeaderField {
-    name: String,
-    typ: String,
+/// Type of the column holding each row's input position (`:o`).
+pub(crate) const ORDER_TYPE: &str = "o";
+
+/// Cell standing for the column default (`name:s@value`).
+pub(crate) const DEFAULT_CELL: &str = "*";
+
+pub(crate) struct HeaderField {
+    pub(crate) name: String,
+    pub(crate) typ: String,
+    /// Enum options; type `e` marks an indexed (`!`) enum or a dictionary
+    /// (`$`) column.
+    pub(crate) options: Arc<Vec<String>>,
+    /// Start and step of an `i+` column.
+    pub(crate) sequence: (i64, i64),
+    /// Base of a `d` or `dt` column whose cells are offsets.
+    pub(crate) base: Option<temporal::Base>,
+    /// Text hoisted from the start of every `s`, `t`, `u` or `x` value.
+    pub(crate) prefix: String,
+    /// Value of the column's `*` cells.
+    pub(crate) default: Option<serde_json::Value>,
 }
 
-struct ConstantField {
-    name: String,
-    val: String,
-    typ: String,
+pub(crate) struct ConstantField {
+    pub(crate) name: String,
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    pub(crate) val: String,
+    /// `val` parsed once, injected into every row.
+    value: serde_json::Value,
+    #[cfg_attr(not(feature = "arrow"), allow(dead_code))]
+    pub(crate) typ: String,
 }
 
-fn parse_value(s: &str, typ: &str) -> serde_json::Value {
+pub(crate) fn parse_value(s: &str, typ: &str) -> serde_json::Value {
     if s == "~" {
         return serde_json::Value::Null;
     }
 
     match typ {
-        "i" | "i+" => {
+        "i" | "i+" | "r" => {
             if let Ok(n) = s.parse::<i64>() {
                 return serde_json::Value::Number(n.into());
             }
@@ -363,18 +1129,113 @@ fn parse_value(s: &str, typ: &str) -> serde_json::Value {
         "b" => {
             return serde_json::Value::Bool(s == "1" || s == "y" || s == "true");
         }
+        // Spec 11.3: string columns are strings whatever the token looks
+        // like. Bare `t` cells are read by their token, as the column may
+        // mix strings with other values.
+        "s" => return serde_json::Value::String(s.replace('_', " ")),
         _ => {}
     }
 
+    if matches!(typ, "a" | "t" | "auto") && s.starts_with('[') && s.ends_with(']') {
+        return serde_json::Value::Array(parse_items(&s[1..s.len() - 1]));
+    }
+
     if s == "y" || s == "n" {
         return serde_json::Value::Bool(s == "y");
     }
     if let Ok(n) = s.parse::<i64>() {
         return serde_json::Value::Number(n.into());
     }
+    if let Ok(n) = s.parse::<u64>() {
+        return serde_json::Value::Number(n.into());
+    }
+    if is_decimal(s) {
+        if let Some(n) = s.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+            return serde_json::Value::Number(n);
+        }
+    }
     if s == "true" || s == "false" {
         return serde_json::Value::Bool(s == "true");
     }
 
     serde_json::Value::String(s.replace('_', " "))
 }
+
+/// Items of an `[a,b]` value. Quoted items are JSON strings and nested
+/// arrays and objects are JSON; other items are scalars.
+fn parse_items(inner: &str) -> Vec<serde_json::Value> {
+    if inner.is_empty() {
+        return Vec::new();
+    }
+    let bytes = inner.as_bytes();
+    let mut items = Vec::new();
+    let (mut start, mut pos) = (0, 0);
+    while pos <= bytes.len() {
+        match bytes.get(pos) {
+            Some(b'"') => pos = quoted_end(bytes, pos),
+            Some(b'[' | b'{') => pos = bracket_end(bytes, pos).unwrap_or(bytes.len()),
+            Some(b',') | None => {
+                let item = &inner[start..pos];
+                items.push(match item.as_bytes().first() {
+                    Some(b'"' | b'[' | b'{') => serde_json::from_str(item).unwrap_or_else(|_| serde_json::Value::String(item.to_string())),
+                    _ => parse_value(item, "auto"),
+                });
+                pos += 1;
+                start = pos;
+            }
+            Some(_) => pos += 1,
+        }
+    }
+    items
+}
+
+/// End of the quoted string opening at `start`, past its closing quote.
+fn quoted_end(bytes: &[u8], start: usize) -> usize {
+    let mut pos = start + 1;
+    while pos < bytes.len() {
+        match bytes[pos] {
+            b'\\' => pos += 2,
+            b'"' => return pos + 1,
+            _ => pos += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// Text of a quoted cell, without its quotes and escapes. Quoted cells are
+/// strings in every column type.
+pub(crate) fn quoted_text(token: &str) -> String {
+    let inner = token.strip_prefix('"').unwrap_or(token);
+    unescape_text(inner.strip_suffix('"').unwrap_or(inner))
+}
+
+/// Reverses the `\"`, `\\`, `\n` and `\r` escapes of a quoted cell.
+fn unescape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            if let Some(next) = chars.next() {
+                out.push(match next {
+                    'n' => '\n',
+                    'r' => '\r',
+                    other => other,
+                });
+                continue;
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Matches the spec's numeric pattern `-?\d+(\.\d+)?`.
+pub(crate) fn is_decimal(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    let (int_part, frac_part) = match digits.split_once('.') {
+        Some((int_part, frac_part)) => (int_part, Some(frac_part)),
+        None => (digits, None),
+    };
+    let all_digits = |p: &str| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit());
+    all_digits(int_part) && frac_part.is_none_or(all_digits)
+}
@@ -1,24 +1,147 @@
-use crate::{Result, ZoonError};
+use crate::decode::{is_header_line, DEFAULT_CELL, ORDER_TYPE};
+use crate::{ident, temporal};
+use crate::{Metadata, Result, ZoonError};
 use serde::Serialize;
 use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+/// Measures output text in tokens of the target model.
+///
+/// `cost` is called with candidate fragments such as `" status!a|b"` or
+/// `" 2"`, so leading spaces matter the same way they do in the full output.
+pub trait TokenCost {
+    fn cost(&self, text: &str) -> usize;
+}
+
+#[derive(Clone, Default)]
+pub struct EncodeOptions {
+    /// Cost model consulted when choosing literal vs indexed enums, `s` vs
+    /// `t` columns, quoting vs hoisting a prefix and which prefixes to alias. Without one the encoder
+    /// compares character counts.
+    pub cost_model: Option<Arc<dyn TokenCost + Send + Sync>>,
+    /// Comment and metadata lines written ahead of the document.
+    pub metadata: Metadata,
+    /// Move sub-objects that repeat across the rows of a top-level array
+    /// into lookup sections, leaving a reference column (SPEC §4.8).
+    pub normalize: bool,
+    /// Rearrange columns, and optionally rows, of top-level tables to
+    /// shrink them (SPEC §4.13).
+    pub reorder: Reorder,
+    /// Spread inline documents over lines for people to read and edit
+    /// (SPEC §8.5).
+    pub pretty: Pretty,
+}
+
+/// How the encoder may rearrange a table when order does not matter to
+/// the reader.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Reorder {
+    /// Columns and rows in input order.
+    #[default]
+    Off,
+    /// Columns with the most nulls last, so rows can leave out trailing
+    /// nulls.
+    Columns,
+    /// As `Columns`, with rows sorted whichever way encodes smallest.
+    /// Decoded rows come back in the sorted order.
+    Rows,
+    /// As `Rows`, with an order (`:o`) column when sorting pays for it,
+    /// so decoders put rows back in input order.
+    RowsKeepingOrder,
+}
+
+/// How an inline document is spread over lines.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Pretty {
+    /// All entries on one line.
+    #[default]
+    Off,
+    /// Each top-level entry on its own line.
+    TopLevel,
+    /// As `TopLevel`, with the entries of braced objects on their own
+    /// lines too, indented two spaces per level.
+    Nested,
+}
+
+impl std::fmt::Debug for EncodeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncodeOptions")
+            .field("cost_model", &self.cost_model.as_ref().map(|_| ".."))
+            .field("metadata", &self.metadata)
+            .field("normalize", &self.normalize)
+            .field("reorder", &self.reorder)
+            .field("pretty", &self.pretty)
+            .finish()
+    }
+}
 
 pub fn encode<T: Serialize>(value: &T) -> Result<String> {
+    encode_with_options(value, &EncodeOptions::default())
+}
+
+pub fn encode_with_options<T: Serialize>(value: &T, options: &EncodeOptions) -> Result<String> {
     let json_value = serde_json::to_value(value).map_err(|e| ZoonError::UnsupportedType(e.to_string()))?;
-    encode_value(&json_value)
+    let cost = options.cost_model.as_deref().map(|c| c as &dyn TokenCost);
+    let body = match &json_value {
+        serde_json::Value::Array(arr) if options.normalize => encode_normalized(arr, cost, options.reorder)?,
+        _ => encode_value(&json_value, cost, options.reorder, options.pretty)?,
+    };
+    with_metadata(body, &options.metadata)
+}
+
+/// Encodes each field of a struct or map as a `## name` section: arrays as
+/// tables, objects inline.
+pub fn encode_sections<T: Serialize>(value: &T) -> Result<String> {
+    encode_sections_with_options(value, &EncodeOptions::default())
 }
 
-fn encode_value(value: &serde_json::Value) -> Result<String> {
+pub fn encode_sections_with_options<T: Serialize>(value: &T, options: &EncodeOptions) -> Result<String> {
+    let json_value = serde_json::to_value(value).map_err(|e| ZoonError::UnsupportedType(e.to_string()))?;
+    let serde_json::Value::Object(sections) = json_value else {
+        return Err(ZoonError::InvalidFormat("sections must come from a struct or map".into()));
+    };
+    let cost = options.cost_model.as_deref().map(|c| c as &dyn TokenCost);
+
+    let mut lines = Vec::new();
+    for (name, section) in &sections {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(ZoonError::InvalidFormat(format!("invalid section name {:?}", name)));
+        }
+        if !section.is_array() && !section.is_object() {
+            return Err(ZoonError::UnsupportedType(format!("section {} must be an array or object", name)));
+        }
+        lines.push(format!("## {}", name));
+        let body = encode_value(section, cost, options.reorder, options.pretty)?;
+        if !body.is_empty() {
+            lines.push(body);
+        }
+    }
+    with_metadata(lines.join("\n"), &options.metadata)
+}
+
+fn with_metadata(body: String, metadata: &Metadata) -> Result<String> {
+    if metadata.is_empty() {
+        return Ok(body);
+    }
+    let lines = metadata.to_lines()?;
+    Ok(if body.is_empty() { lines } else { format!("{}\n{}", lines, body) })
+}
+
+fn encode_value(value: &serde_json::Value, cost: Option<&dyn TokenCost>, reorder: Reorder, pretty: Pretty) -> Result<String> {
     match value {
-        serde_json::Value::Array(arr) => encode_tabular(arr),
-        serde_json::Value::Object(obj) => encode_inline(obj),
+        serde_json::Value::Array(arr) => encode_tabular(arr, cost, &[], reorder),
+        serde_json::Value::Object(obj) => encode_inline(obj, cost, pretty),
         _ => Err(ZoonError::InvalidFormat("top level must be object or array".into())),
     }
 }
 
-fn flatten_object(
+pub(crate) fn flatten_object(
     prefix: &str,
     value: &serde_json::Value,
-    result: &mut BTreeMap<String, serde_json::Value>,
+    result: &mut impl Extend<(String, serde_json::Value)>,
 ) {
     if let serde_json::Value::Object(obj) = value {
         for (k, v) in obj {
@@ -30,219 +153,571 @@ fn flatten_object(
             flatten_object(&new_key, v, result);
         }
     } else {
-        result.insert(prefix.to_string(), value.clone());
+        result.extend([(prefix.to_string(), value.clone())]);
     }
 }
 
-fn detect_aliases(keys: &[String]) -> HashMap<String, String> {
-    let mut prefix_counts: HashMap<String, usize> = HashMap::new();
-    
-    for key in keys {
-        let parts: Vec<&str> = key.split('.').collect();
-        if parts.len() > 1 {
-            for i in 1..parts.len() {
-                let prefix = parts[..i].join(".");
-                *prefix_counts.entry(prefix).or_insert(0) += 1;
+/// Chooses which key prefixes to alias and names them.
+///
+/// Returns `(prefix, alias)` pairs in definition order, parents before the
+/// aliases nested in them. Every candidate prefix is weighed against the
+/// others: an alias for `a.b` changes what an alias for `a` saves, so the
+/// selection is the cheapest combination over the whole prefix tree rather
+/// than a ranking of independent scores.
+pub(crate) fn detect_aliases(keys: &[String], cost: Option<&dyn TokenCost>) -> Vec<(String, String)> {
+    let mut planner = AliasPlanner::new(keys, cost);
+    let mut selected = Vec::new();
+    for root in planner.roots() {
+        planner.select(root, None, &mut selected);
+    }
+    selected.sort_unstable();
+    selected.into_iter().map(|node| (planner.prefixes[node].clone(), planner.names[node].clone())).collect()
+}
+
+/// Alias names to try for `prefix`, best first: the initials of its
+/// segments (`sp` for `services.postgres`), then longer starts of the last
+/// segment (`spo`, `spos`...), then the initials numbered (`sp2`, `sp3`...).
+/// The sequence is endless, so a free name always exists.
+fn alias_candidates(prefix: &str) -> impl Iterator<Item = String> {
+    let segments: Vec<String> = prefix
+        .split('.')
+        .map(|s| s.chars().filter(char::is_ascii_alphanumeric).map(|c| c.to_ascii_lowercase()).collect::<String>())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let (last, parents) = match segments.split_last() {
+        Some((last, parents)) => (last.clone(), parents.iter().filter_map(|s| s.chars().next()).collect::<String>()),
+        None => ("a".to_string(), String::new()),
+    };
+    let initials = format!("{}{}", parents, &last[..1]);
+    let longer: Vec<String> = (1..=last.len()).map(|n| format!("{}{}", parents, &last[..n])).collect();
+    longer.into_iter().chain((2..).map(move |n| format!("{}{}", initials, n)))
+}
+
+/// Tree of the dotted prefixes of a table's keys, with the cost of each
+/// aliasing choice memoized per node.
+struct AliasPlanner<'a> {
+    /// Candidate prefixes in order of first appearance, parents first.
+    prefixes: Vec<String>,
+    /// Alias of each prefix, distinct across the tree.
+    names: Vec<String>,
+    parents: Vec<Option<usize>>,
+    children: Vec<Vec<usize>>,
+    /// Keys sitting directly under each prefix.
+    keys: Vec<Vec<&'a str>>,
+    cost: Option<&'a dyn TokenCost>,
+    /// Cost of a subtree, and whether its root takes an alias, keyed by
+    /// node and nearest aliased ancestor.
+    memo: HashMap<(usize, Option<usize>), (isize, bool)>,
+}
+
+impl<'a> AliasPlanner<'a> {
+    fn new(keys: &'a [String], cost: Option<&'a dyn TokenCost>) -> Self {
+        let mut planner = AliasPlanner {
+            prefixes: Vec::new(),
+            names: Vec::new(),
+            parents: Vec::new(),
+            children: Vec::new(),
+            keys: Vec::new(),
+            cost,
+            memo: HashMap::new(),
+        };
+        let mut index: HashMap<&str, usize> = HashMap::new();
+        for key in keys {
+            let mut parent = None;
+            // Prefixes holding whitespace cannot be written on the alias line.
+            for (end, _) in key.match_indices('.').take_while(|(end, _)| !key[..*end].contains(char::is_whitespace)) {
+                let prefix = &key[..end];
+                let node = *index.entry(prefix).or_insert_with(|| {
+                    planner.prefixes.push(prefix.to_string());
+                    planner.parents.push(parent);
+                    planner.children.push(Vec::new());
+                    planner.keys.push(Vec::new());
+                    if let Some(p) = parent {
+                        planner.children[p].push(planner.prefixes.len() - 1);
+                    }
+                    planner.prefixes.len() - 1
+                });
+                parent = Some(node);
+            }
+            if let Some(node) = parent.filter(|n| key[planner.prefixes[*n].len() + 1..].find('.').is_none()) {
+                planner.keys[node].push(key);
+            }
+        }
+
+        // Names are fixed before selection so costs use the real ones.
+        // Prefixes covering more keys pick first, then by first appearance.
+        let mut covered = vec![0usize; planner.prefixes.len()];
+        for (node, keys) in planner.keys.iter().enumerate() {
+            let mut at = Some(node);
+            while let Some(n) = at {
+                covered[n] += keys.len();
+                at = planner.parents[n];
             }
         }
+        let mut order: Vec<usize> = (0..planner.prefixes.len()).collect();
+        order.sort_by_key(|n| (std::cmp::Reverse(covered[*n]), *n));
+        planner.names = vec![String::new(); planner.prefixes.len()];
+        let mut used = HashSet::new();
+        for node in order {
+            let alias = alias_candidates(&planner.prefixes[node]).find(|a| !used.contains(a)).unwrap();
+            used.insert(alias.clone());
+            planner.names[node] = alias;
+        }
+        planner
     }
 
-    let mut savings: Vec<(String, isize)> = Vec::new();
+    fn roots(&self) -> Vec<usize> {
+        (0..self.prefixes.len()).filter(|n| self.parents[*n].is_none()).collect()
+    }
 
-    for (prefix, count) in &prefix_counts {
-        let prefix_len = prefix.len() as isize;
-        let count = *count as isize;
-        // Formula: (prefix - 2) * count - (prefix + 4)
-        let score = (prefix_len - 2) * count - (prefix_len + 4);
-        if score > 0 {
-            savings.push((prefix.clone(), score));
+    fn measure(&self, s: &str) -> isize {
+        match self.cost {
+            Some(cost) => cost.cost(s) as isize,
+            None => s.len() as isize,
         }
     }
-    
-    savings.sort_by(|a, b| b.1.cmp(&a.1));
-    
-    let mut aliases = HashMap::new();
-    let mut used_aliases = HashSet::new();
-    let mut alias_idx = 0;
-    
-    for (prefix, _) in savings {
-        // Simple alias assignment
-        let parts: Vec<&str> = prefix.split('.').collect();
-        let name = parts.last().unwrap();
-        let mut candidate = name.chars().next().unwrap().to_lowercase().to_string();
-        
-        // Find valid alias
-        loop {
-            if !used_aliases.contains(&candidate) {
-                break;
+
+    /// `name` written through the alias of `node`, if any.
+    fn render(&self, name: &str, node: Option<usize>) -> String {
+        match node {
+            Some(n) => format!("%{}{}", self.names[n], &name[self.prefixes[n].len()..]),
+            None => name.to_string(),
+        }
+    }
+
+    /// Cheapest cost of the subtree at `node`, given its nearest aliased
+    /// ancestor.
+    fn plan(&mut self, node: usize, ancestor: Option<usize>) -> (isize, bool) {
+        if let Some(plan) = self.memo.get(&(node, ancestor)) {
+            return *plan;
+        }
+        let subtree = |planner: &mut Self, through: Option<usize>| -> isize {
+            let keys: isize = planner.keys[node].iter().map(|k| planner.measure(&format!(" {}", planner.render(k, through)))).sum();
+            let children: isize = planner.children[node].clone().into_iter().map(|c| planner.plan(c, through).0).sum();
+            keys + children
+        };
+        let without = subtree(self, ancestor);
+        let definition = format!(" %{}={}", self.names[node], self.render(&self.prefixes[node], ancestor));
+        let with = self.measure(&definition) + subtree(self, Some(node));
+        let plan = if with < without { (with, true) } else { (without, false) };
+        self.memo.insert((node, ancestor), plan);
+        plan
+    }
+
+    fn select(&mut self, node: usize, ancestor: Option<usize>, selected: &mut Vec<usize>) {
+        let through = if self.plan(node, ancestor).1 {
+            selected.push(node);
+            Some(node)
+        } else {
+            ancestor
+        };
+        for child in self.children[node].clone() {
+            self.select(child, through, selected);
+        }
+    }
+}
+
+/// Definitions of `aliases`, each prefix written through the aliases
+/// defined before it.
+fn alias_line(aliases: &[(String, String)]) -> Option<String> {
+    let defs: Vec<String> = aliases
+        .iter()
+        .enumerate()
+        .map(|(i, (prefix, alias))| format!("%{}={}", alias, apply_alias(prefix, &aliases[..i])))
+        .collect();
+    (!defs.is_empty()).then(|| defs.join(" "))
+}
+
+/// `name` written through the longest matching alias.
+fn apply_alias(name: &str, aliases: &[(String, String)]) -> String {
+    let best = aliases
+        .iter()
+        .filter(|(prefix, _)| name == prefix.as_str() || name.starts_with(&format!("{}.", prefix)))
+        .max_by_key(|(prefix, _)| prefix.len());
+    match best {
+        Some((prefix, alias)) if name == prefix => format!("%{}", alias),
+        Some((prefix, alias)) => format!("%{}.{}", alias, &name[prefix.len() + 1..]),
+        None => name.to_string(),
+    }
+}
+
+/// A sub-object column moved into its own table.
+struct Lookup {
+    key: String,
+    table: Vec<serde_json::Value>,
+    /// Row index into `table` for each input row, `None` for null or absent.
+    indices: Vec<Option<usize>>,
+}
+
+/// Finds top-level keys whose values are objects that repeat across rows.
+fn find_lookups(rows: &[serde_json::Value]) -> Vec<Lookup> {
+    let mut keys: Vec<&String> = Vec::new();
+    for row in rows {
+        let Some(obj) = row.as_object() else { return Vec::new() };
+        for key in obj.keys() {
+            if !keys.contains(&key) {
+                keys.push(key);
             }
-            if candidate.len() == 1 {
-                // a, b, c...
-                let c = (b'a' + alias_idx) as char;
-                candidate = c.to_string();
-                alias_idx += 1;
-                if alias_idx > 25 { break; } // safety
-            } else {
-                // give up or try something else?
-                break;
+        }
+    }
+
+    let mut lookups = Vec::new();
+    'keys: for key in keys {
+        // The key becomes a section name and must survive unflattening and
+        // the `key>key` header field.
+        if key.is_empty() || key.contains(char::is_whitespace) || key.contains(['.', ':', '=', '!', '>']) {
+            continue;
+        }
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut table = Vec::new();
+        let mut indices = Vec::with_capacity(rows.len());
+        let mut objects = 0;
+        for row in rows {
+            match row.get(key) {
+                None | Some(serde_json::Value::Null) => indices.push(None),
+                Some(value @ serde_json::Value::Object(obj)) if !obj.is_empty() => {
+                    objects += 1;
+                    let idx = *seen.entry(value.to_string()).or_insert_with(|| {
+                        table.push(value.clone());
+                        table.len() - 1
+                    });
+                    indices.push(Some(idx));
+                }
+                _ => continue 'keys,
             }
         }
-        
-        if !used_aliases.contains(&candidate) {
-            aliases.insert(prefix, candidate.clone());
-            used_aliases.insert(candidate);
+        // A single distinct object is cheaper as hoisted constants.
+        if table.len() >= 2 && table.len() < objects {
+            lookups.push(Lookup { key: key.clone(), table, indices });
         }
-        if aliases.len() >= 10 { break; }
     }
-    
-    aliases
+    lookups
 }
 
-fn apply_alias(name: &str, aliases: &HashMap<String, String>) -> String {
-    for (prefix, alias) in aliases {
-        if name == prefix {
-            return format!("%{}", alias);
-        }
-        if name.starts_with(&format!("{}.", prefix)) {
-            return format!("%{}.{}", alias, &name[prefix.len() + 1..]);
+/// Writes each lookup as a `## key` section and the rows, with references
+/// in place of the repeated objects, as the unnamed `##` section. Lookup
+/// rows keep their order, which references depend on.
+fn encode_normalized(arr: &[serde_json::Value], cost: Option<&dyn TokenCost>, reorder: Reorder) -> Result<String> {
+    let lookups = find_lookups(arr);
+    if lookups.is_empty() {
+        return encode_tabular(arr, cost, &[], reorder);
+    }
+
+    let mut rows = arr.to_vec();
+    let mut lines = Vec::new();
+    let mut refs = Vec::new();
+    for lookup in &lookups {
+        for (row, idx) in rows.iter_mut().zip(&lookup.indices) {
+            if let Some(obj) = row.as_object_mut() {
+                if let Some(idx) = idx {
+                    obj.insert(lookup.key.clone(), serde_json::Value::from(*idx));
+                }
+            }
         }
+        lines.push(format!("## {}", lookup.key));
+        lines.push(encode_tabular(&lookup.table, cost, &[], Reorder::Off)?);
+        refs.push((lookup.key.clone(), format!(">{}", lookup.key)));
     }
-    name.to_string()
+    lines.push("##".to_string());
+    lines.push(encode_tabular(&rows, cost, &refs, reorder)?);
+    Ok(lines.join("\n"))
 }
 
-fn encode_tabular(arr: &[serde_json::Value]) -> Result<String> {
+/// `forced` pairs columns with a fixed type: references (`>table`) and
+/// the order column.
+fn encode_tabular(
+    arr: &[serde_json::Value],
+    cost: Option<&dyn TokenCost>,
+    forced: &[(String, String)],
+    reorder: Reorder,
+) -> Result<String> {
     if arr.is_empty() {
         return Ok(String::new());
     }
 
-    // 1. Flatten
+    // 1. Flatten, keeping keys in encounter order (spec 10.3)
     let mut flattened_rows = Vec::new();
     let mut all_keys_set = HashSet::new();
+    let mut all_keys: Vec<String> = Vec::new();
 
     for item in arr {
-        let mut flat_map = BTreeMap::new();
-        flatten_object("", item, &mut flat_map);
-        for k in flat_map.keys() {
-            all_keys_set.insert(k.clone());
+        let mut flat_pairs = Vec::new();
+        flatten_object("", item, &mut flat_pairs);
+        for (k, _) in &flat_pairs {
+            if all_keys_set.insert(k.clone()) {
+                all_keys.push(k.clone());
+            }
         }
-        flattened_rows.push(flat_map);
+        flattened_rows.push(flat_pairs.into_iter().collect::<BTreeMap<_, _>>());
     }
-    
-    let mut all_keys: Vec<String> = all_keys_set.into_iter().collect();
-    all_keys.sort();
-    
+
+    if reorder == Reorder::Off {
+        return Ok(write_rows(&flattened_rows, &all_keys, cost, forced));
+    }
+    // Stable, so columns with as many nulls keep their order.
+    all_keys.sort_by_cached_key(|key| flattened_rows.iter().filter(|row| row.get(key).is_none_or(|v| v.is_null())).count());
+    Ok(match reorder {
+        Reorder::Rows | Reorder::RowsKeepingOrder => {
+            sorted_table(&flattened_rows, &all_keys, cost, forced, reorder == Reorder::RowsKeepingOrder)
+        }
+        _ => trim_trailing_nulls(&write_rows(&flattened_rows, &all_keys, cost, forced)),
+    })
+}
+
+/// Key of the order column, which the encoder names with an empty string.
+const ORDER_KEY: &str = "";
+
+/// Encodes the rows in input order and in each order from [`row_orders`],
+/// keeping the smallest result; input order wins ties. With `keep_order`,
+/// sorted rows carry their input position in an order (`o`) column.
+fn sorted_table(
+    rows: &[BTreeMap<String, serde_json::Value>],
+    keys: &[String],
+    cost: Option<&dyn TokenCost>,
+    forced: &[(String, String)],
+    keep_order: bool,
+) -> String {
+    let measure = |text: &str| cost.map_or(text.len(), |c| c.cost(text));
+    let mut best = trim_trailing_nulls(&write_rows(rows, keys, cost, forced));
+    // A real empty key would clash with the order column.
+    if keep_order && keys.iter().any(|k| k == ORDER_KEY) {
+        return best;
+    }
+    let mut best_cost = measure(&best);
+    for order in row_orders(rows, keys) {
+        let mut sorted: Vec<BTreeMap<String, serde_json::Value>> = order.iter().map(|i| rows[*i].clone()).collect();
+        let mut keys = keys.to_vec();
+        let mut forced = forced.to_vec();
+        if keep_order {
+            for (row, i) in sorted.iter_mut().zip(&order) {
+                row.insert(ORDER_KEY.to_string(), serde_json::Value::from(*i));
+            }
+            keys.insert(0, ORDER_KEY.to_string());
+            forced.push((ORDER_KEY.to_string(), ORDER_TYPE.to_string()));
+        }
+        let table = trim_trailing_nulls(&write_rows(&sorted, &keys, cost, &forced));
+        let table_cost = measure(&table);
+        if table_cost < best_cost {
+            (best, best_cost) = (table, table_cost);
+        }
+    }
+    best
+}
+
+/// Row orders worth trying: all columns as sort keys, fewest distinct
+/// values first so equal values end up together, then each numeric column
+/// on its own ahead of those, which can turn it into a sequence or delta.
+fn row_orders(rows: &[BTreeMap<String, serde_json::Value>], keys: &[String]) -> Vec<Vec<usize>> {
+    let mut by_distinct = keys.to_vec();
+    by_distinct.sort_by_cached_key(|key| rows.iter().map(|row| row.get(key).map(|v| v.to_string())).collect::<HashSet<_>>().len());
+    let numeric = keys.iter().filter(|key| rows.iter().all(|row| row.get(*key).is_none_or(|v| v.is_number() || v.is_null())));
+
+    let mut key_lists = vec![by_distinct.clone()];
+    for key in numeric {
+        let mut list = vec![key.clone()];
+        list.extend(by_distinct.iter().filter(|k| *k != key).cloned());
+        key_lists.push(list);
+    }
+
+    let identity: Vec<usize> = (0..rows.len()).collect();
+    let mut orders: Vec<Vec<usize>> = Vec::new();
+    for list in key_lists {
+        let mut order = identity.clone();
+        order.sort_by(|a, b| {
+            list.iter()
+                .map(|key| compare_cells(rows[*a].get(key), rows[*b].get(key)))
+                .find(|o| o.is_ne())
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        if order != identity && !orders.contains(&order) {
+            orders.push(order);
+        }
+    }
+    orders
+}
+
+/// Orders cells by kind (numbers, strings, booleans, the rest, then
+/// nulls) and by value within a kind.
+fn compare_cells(a: Option<&serde_json::Value>, b: Option<&serde_json::Value>) -> std::cmp::Ordering {
+    use serde_json::Value;
+    let rank = |v: Option<&Value>| match v {
+        Some(Value::Number(_)) => 0,
+        Some(Value::String(_)) => 1,
+        Some(Value::Bool(_)) => 2,
+        Some(Value::Array(_) | Value::Object(_)) => 3,
+        None | Some(Value::Null) => 4,
+    };
+    rank(a).cmp(&rank(b)).then_with(|| match (a, b) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => {
+            x.as_f64().partial_cmp(&y.as_f64()).unwrap_or(std::cmp::Ordering::Equal)
+        }
+        (Some(Value::String(x)), Some(Value::String(y))) => x.cmp(y),
+        (Some(Value::Bool(x)), Some(Value::Bool(y))) => x.cmp(y),
+        (Some(x), Some(y)) => x.to_string().cmp(&y.to_string()),
+        _ => std::cmp::Ordering::Equal,
+    })
+}
+
+/// Drops `~` cells from the end of body rows, which decoders read back as
+/// null. A row keeps its first cell so it does not become a blank line.
+fn trim_trailing_nulls(table: &str) -> String {
+    let mut in_body = false;
+    let lines: Vec<&str> = table
+        .lines()
+        .map(|line| {
+            if !in_body {
+                in_body = is_header_line(line);
+                return line;
+            }
+            let mut row = line;
+            while let Some(rest) = row.strip_suffix(" ~") {
+                row = rest;
+            }
+            row
+        })
+        .collect();
+    lines.join("\n")
+}
+
+/// Hoists constants, types the columns and writes the table for rows
+/// flattened over `all_keys`.
+fn write_rows(
+    flattened_rows: &[BTreeMap<String, serde_json::Value>],
+    all_keys: &[String],
+    cost: Option<&dyn TokenCost>,
+    forced: &[(String, String)],
+) -> String {
+    let row_count = flattened_rows.len();
+
     // 2. Constants
-    let mut constants = BTreeMap::new();
+    let mut constants = Vec::new();
     let mut active_keys = Vec::new();
-    
-    if arr.len() > 1 {
-        for key in &all_keys {
+
+    if row_count > 1 {
+        for key in all_keys {
             let first_val = flattened_rows[0].get(key).unwrap_or(&serde_json::Value::Null);
             let mut is_const = true;
-            for row in &flattened_rows {
+            for row in flattened_rows {
                 let val = row.get(key).unwrap_or(&serde_json::Value::Null);
                 if val != first_val {
                     is_const = false;
                     break;
                 }
             }
-            if is_const && !first_val.is_null() {
-                constants.insert(key.clone(), first_val.clone());
+            let is_forced = forced.iter().any(|(k, _)| k == key);
+            if is_const && !is_forced && hoistable(first_val) {
+                constants.push((key.clone(), first_val.clone()));
             } else {
                 active_keys.push(key.clone());
             }
         }
     } else {
-        active_keys = all_keys;
+        active_keys = all_keys.to_vec();
     }
     
-    // 3. Aliases
-    let aliases = detect_aliases(&active_keys);
+    // 3. Stats and cells, one column at a time
+    #[cfg(feature = "rayon")]
+    let keys = active_keys.par_iter();
+    #[cfg(not(feature = "rayon"))]
+    let keys = active_keys.iter();
+    let stats: Vec<ColumnStats> = keys.map(|key| column_stats(flattened_rows, key)).collect();
 
-    // 4. Stats
-    let mut stats: BTreeMap<String, ColumnStats> = BTreeMap::new();
-    for key in &active_keys {
-        stats.insert(key.clone(), ColumnStats::default());
-    }
-    
-    for row in &flattened_rows {
-        for key in &active_keys {
-            let val = row.get(key).unwrap_or(&serde_json::Value::Null);
-            let s = serialize_value(val);
-            let stat = stats.get_mut(key).unwrap();
-            
-            stat.values.push(s.clone());
-            stat.unique_vals.insert(s);
-            
-            // Guess is likely ID (simplified logic)
-            if key.to_lowercase() == "id" {
-                if let serde_json::Value::Number(_) = val {
-                    stat.is_seq = true;
-                }
-            }
-        }
+    // Type inference may consult the cost model, which is not `Sync`.
+    let mut type_codes: Vec<String> = active_keys
+        .iter()
+        .zip(&stats)
+        .map(|(key, stat)| match forced.iter().find(|(k, _)| k == key) {
+            Some((_, type_code)) => type_code.clone(),
+            None => with_prefix(with_default(infer_type(stat, row_count, key, cost), stat, key, cost), stat, cost),
+        })
+        .collect();
+    let dictionary = choose_dictionary(&mut type_codes, &stats, cost);
+    let entry_index: HashMap<&str, usize> = dictionary.iter().enumerate().map(|(i, e)| (e.as_str(), i)).collect();
+
+    #[cfg(feature = "rayon")]
+    let typed = active_keys.par_iter().zip(type_codes).zip(&stats);
+    #[cfg(not(feature = "rayon"))]
+    let typed = active_keys.iter().zip(type_codes).zip(&stats);
+    let columns: Vec<TableColumn> = typed
+        .map(|((key, type_code), stat)| {
+            let cells = if type_code.starts_with("i+") {
+                Vec::new()
+            } else if type_code == "i^" {
+                delta_cells(&stat.values)
+            } else if type_code == DICTIONARY_TYPE {
+                stat.values.iter().map(|v| entry_index.get(v.as_str()).map_or_else(|| v.clone(), |i| i.to_string())).collect()
+            } else if type_code.starts_with('d') {
+                temporal_cells(&type_code, &stat.values)
+            } else if let Some((typ @ ("s" | "i" | "b"), default)) = type_code.split_once('@') {
+                // Only `with_default` codes; enum options may hold `@` too.
+                flattened_rows
+                    .iter()
+                    .zip(&stat.values)
+                    .map(|(row, value)| match value == default {
+                        true => DEFAULT_CELL.to_string(),
+                        false => format_cell(row.get(key).unwrap_or(&serde_json::Value::Null), typ),
+                    })
+                    .collect()
+            } else {
+                flattened_rows
+                    .iter()
+                    .map(|row| format_cell(row.get(key).unwrap_or(&serde_json::Value::Null), &type_code))
+                    .collect()
+            };
+            TableColumn { name: key.clone(), type_code, cells }
+        })
+        .collect();
+    let constants: Vec<(String, String)> = constants
+        .iter()
+        .map(|(k, v)| (k.clone(), format_constant(v)))
+        .collect();
+
+    let table = write_table(&constants, &columns, row_count, cost);
+    if dictionary.is_empty() {
+        table
+    } else {
+        format!("$ {}\n{}", dictionary.join(" "), table)
     }
-    
+}
+
+/// A tabular column ready to be written.
+///
+/// `cells` is empty for columns that are not stored in the body (`i+`).
+/// Delta (`i^`) cells are already differences.
+pub(crate) struct TableColumn {
+    pub(crate) name: String,
+    pub(crate) type_code: String,
+    pub(crate) cells: Vec<String>,
+}
+
+/// Assembles alias line, header and body from prepared columns.
+///
+/// `constants` pairs a field name with its hoisted value, separator included
+/// (`=INFO`, `:42`).
+pub(crate) fn write_table(
+    constants: &[(String, String)],
+    columns: &[TableColumn],
+    row_count: usize,
+    cost: Option<&dyn TokenCost>,
+) -> String {
+    let names: Vec<String> = columns.iter().map(|c| c.name.clone()).collect();
+    let aliases = detect_aliases(&names, cost);
+
     // Build Header
     let mut header_parts = vec!["#".to_string()];
-    
-    // Alias definitions
-    let mut alias_defs: Vec<String> = Vec::new();
-    for (prefix, alias) in &aliases {
-        alias_defs.push(format!("%{}={}", alias, prefix));
-    }
-    alias_defs.sort(); // Deterministic
-    
-    // Combine alias defs into lines before header?
-    // Spec: "%a=... # ..." or separate lines.
-    // ZOON 1.1 spec usually puts alias defs on new line or same line.
-    // My Go/Python impl use separate lines if aliases exist.
-    // But function returns one String.
-    
-    let mut lines = Vec::new();
-    if !alias_defs.is_empty() {
-        lines.push(alias_defs.join(" "));
-    }
+
+    let mut lines: Vec<String> = alias_line(&aliases).into_iter().collect();
 
     // Constants
-    for (k, v) in &constants {
+    for (k, v) in constants {
         let aliased = apply_alias(k, &aliases).replace(" ", "_");
-        let s_val = serialize_value(v);
-        let mut type_code = ":";
-        
-        if let serde_json::Value::String(_) = v {
-            type_code = "=";
-        } else if let serde_json::Value::Bool(b) = v {
-             if *b {
-                 // s_val is "1", but for constant display "y" is nicer or standard?
-                 // Spec: @active:y or @active:n
-                 // serialize_value returns "1" or "0".
-                 // We should fix this.
-             }
-        }
-        
-        let display_val = if let serde_json::Value::Bool(b) = v {
-            if *b { "y".to_string() } else { "n".to_string() }
-        } else {
-            s_val
-        };
-        
-        let sep = if let serde_json::Value::String(_) = v { "=" } else { ":" };
-        header_parts.push(format!("@{}{}{}", aliased, sep, display_val));
+        header_parts.push(format!("@{}{}", aliased, v));
     }
 
-    let mut skip_indices = HashSet::new();
-    
-    for (i, key) in active_keys.iter().enumerate() {
-        let stat = stats.get(key).unwrap();
-        let aliased = apply_alias(key, &aliases).replace(" ", "_");
-        let type_code = infer_type(stat, arr.len(), key);
-        
-        if type_code == "i+" {
-            skip_indices.insert(i);
-        }
-        
-        if type_code.starts_with('=') || type_code.starts_with('!') {
+    for column in columns {
+        let aliased = apply_alias(&column.name, &aliases).replace(" ", "_");
+        let type_code = &column.type_code;
+        if type_code.starts_with(['=', '!', '>']) {
             header_parts.push(format!("{}{}", aliased, type_code));
         } else {
             header_parts.push(format!("{}:{}", aliased, type_code));
@@ -251,118 +726,546 @@ fn encode_tabular(arr: &[serde_json::Value]) -> Result<String> {
 
     // +N Check
     // Check if all active columns are skipped (i+)
-    let mut all_skipped = true;
-    if active_keys.is_empty() {
-        // all implicit
-    } else {
-        for i in 0..active_keys.len() {
-            if !skip_indices.contains(&i) {
-                all_skipped = false;
-                break;
-            }
-        }
-    }
-    
-    if all_skipped && !arr.is_empty() {
-        header_parts.push(format!("+{}", arr.len()));
+    let all_skipped = columns.iter().all(|c| c.type_code.starts_with("i+"));
+    if all_skipped && row_count > 0 {
+        header_parts.push(format!("+{}", row_count));
     }
 
     lines.push(header_parts.join(" "));
     
     if all_skipped {
-        return Ok(lines.join("\n"));
+        return lines.join("\n");
     }
     
     // Rows
-    for row in &flattened_rows {
-        let mut out_row = Vec::new();
-        for (i, key) in active_keys.iter().enumerate() {
-            if skip_indices.contains(&i) { continue; }
-            
-            let val = row.get(key).unwrap_or(&serde_json::Value::Null);
-            let mut s = serialize_value(val);
-            
-            let stat = stats.get(key).unwrap();
-            let type_code = infer_type(stat, arr.len(), key);
-            
-            if type_code == "b" {
-                 if s == "true" { s = "1".into(); }
-                 else if s == "false" { s = "0".into(); }
-            } else if type_code == "t" {
-                if let serde_json::Value::String(raw) = val {
-                    s = format!("\"{}\"", raw.replace('"', "\\\""));
-                }
+    let body: Vec<&TableColumn> = columns.iter().filter(|c| !c.type_code.starts_with("i+")).collect();
+    for i in 0..row_count {
+        let out_row: Vec<&str> = body.iter().map(|c| c.cells[i].as_str()).collect();
+        lines.push(out_row.join(" "));
+    }
+
+    lines.join("\n")
+}
+
+/// Whether a value can be hoisted into the header as a constant, where
+/// nothing is quoted.
+fn hoistable(v: &serde_json::Value) -> bool {
+    match v {
+        serde_json::Value::Null => false,
+        serde_json::Value::String(s) => !needs_quotes(s),
+        _ => true,
+    }
+}
+
+/// Formats a hoisted constant, separator included.
+pub(crate) fn format_constant(v: &serde_json::Value) -> String {
+    // Spec: @active:y or @active:n, serialize_value returns "1" or "0".
+    match v {
+        serde_json::Value::Bool(b) => format!(":{}", if *b { "y" } else { "n" }),
+        serde_json::Value::String(_) => format!("={}", serialize_value(v)),
+        _ => format!(":{}", serialize_value(v)),
+    }
+}
+
+/// Encodes an inline document: an alias line when repeated dotted
+/// prefixes pay for one, then the entries.
+pub(crate) fn encode_inline(
+    obj: &serde_json::Map<String, serde_json::Value>,
+    cost: Option<&dyn TokenCost>,
+    pretty: Pretty,
+) -> Result<String> {
+    // A top-level key starts a line, which its first characters could turn
+    // into a comment, an alias definition, a header or a section.
+    for key in obj.keys() {
+        let reads_as = if key.starts_with("//") {
+            "a comment"
+        } else if key.starts_with('%') {
+            "an alias"
+        } else if key.starts_with('#') {
+            "a header"
+        } else {
+            continue;
+        };
+        return Err(ZoonError::UnsupportedType(format!("key {:?} would read as {}", key, reads_as)));
+    }
+    // Aliases pay off on long repeated paths, which the layout avoids by
+    // bracing them, so fully dotted entries are tried as well. Candidates
+    // are compared on one line, so `pretty` only moves line breaks.
+    let measure = |(line, entries): &(Option<String>, Vec<InlineEntry>)| {
+        let text = line.iter().cloned().chain(entries.iter().map(InlineEntry::line)).collect::<Vec<_>>().join(" ");
+        cost.map_or(text.len(), |c| c.cost(&text))
+    };
+    let mut layout = Vec::new();
+    InlineLayout::default().entries(obj, "", &mut layout);
+    let mut dotted = Vec::new();
+    dotted_entries(obj, "", &mut dotted);
+    let candidates = [aliased_entries(&layout, cost), aliased_entries(&dotted, cost)];
+    let (line, entries) = candidates
+        .into_iter()
+        .flatten()
+        .map(|(line, entries)| (Some(line), entries))
+        .fold((None, layout), |best, candidate| if measure(&candidate) < measure(&best) { candidate } else { best });
+    let body = match pretty {
+        Pretty::Off => entries.iter().map(InlineEntry::line).collect::<Vec<_>>().join(" "),
+        Pretty::TopLevel => entries.iter().map(InlineEntry::line).collect::<Vec<_>>().join("\n"),
+        Pretty::Nested => entries.iter().map(|entry| entry.spread(0)).collect::<Vec<_>>().join("\n"),
+    };
+    Ok(match line {
+        Some(line) => format!("{}\n{}", line, body),
+        None => body,
+    })
+}
+
+/// Inline entries with their common key prefixes aliased, and the alias
+/// line defining them, or `None` if no prefix is worth an alias.
+fn aliased_entries(entries: &[InlineEntry], cost: Option<&dyn TokenCost>) -> Option<(String, Vec<InlineEntry>)> {
+    let keys: Vec<String> = entries.iter().map(|e| e.key.clone()).collect();
+    let aliases = detect_aliases(&keys, cost);
+    let line = alias_line(&aliases)?;
+    let entries: Vec<InlineEntry> = entries
+        .iter()
+        .map(|entry| {
+            // A bare `%a=...` would read as an alias definition.
+            let aliased = apply_alias(&entry.key, &aliases);
+            let key = if aliased.contains('.') { aliased } else { entry.key.clone() };
+            InlineEntry { key, value: entry.value.clone() }
+        })
+        .collect();
+    Some((line, entries))
+}
+
+/// An entry of an inline document, kept as a tree until it is laid out.
+#[derive(Clone)]
+struct InlineEntry {
+    key: String,
+    value: InlineValue,
+}
+
+#[derive(Clone)]
+enum InlineValue {
+    /// Separator and value, as in `=x`, `:1` or `:{}`.
+    Text(String),
+    /// Entries of a non-empty braced object.
+    Braced(Vec<InlineEntry>),
+}
+
+impl InlineEntry {
+    fn pair(key: String, value: &serde_json::Value) -> Self {
+        InlineEntry { value: InlineValue::Text(inline_value(value)), key }
+    }
+
+    /// The entry on one line.
+    fn line(&self) -> String {
+        match &self.value {
+            InlineValue::Text(text) => format!("{}{}", self.key, text),
+            InlineValue::Braced(children) => {
+                format!("{}:{{{}}}", self.key, children.iter().map(InlineEntry::line).collect::<Vec<_>>().join(" "))
             }
-            out_row.push(s);
         }
-        lines.push(out_row.join(" "));
     }
 
-    Ok(lines.join("\n"))
+    /// The entry with each of a braced object's entries on an indented
+    /// line of its own and the closing brace under the key.
+    fn spread(&self, level: usize) -> String {
+        let InlineValue::Braced(children) = &self.value else {
+            return self.line();
+        };
+        let indent = "  ".repeat(level + 1);
+        let mut lines = vec![format!("{}:{{", self.key)];
+        lines.extend(children.iter().map(|child| format!("{}{}", indent, child.spread(level + 1))));
+        lines.push(format!("{}}}", "  ".repeat(level)));
+        lines.join("\n")
+    }
 }
 
-fn encode_inline(obj: &serde_json::Map<String, serde_json::Value>) -> Result<String> {
-    let parts: Vec<String> = obj.iter().map(|(k, v)| format_inline_pair(k, v)).collect();
-    Ok(parts.join(" "))
+/// Entries of an inline object with every nested object dotted.
+fn dotted_entries(obj: &serde_json::Map<String, serde_json::Value>, path: &str, out: &mut Vec<InlineEntry>) {
+    for (k, v) in obj {
+        let key = if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+        match v {
+            serde_json::Value::Object(inner) if !inner.is_empty() => dotted_entries(inner, &key, out),
+            _ => out.push(InlineEntry::pair(key, v)),
+        }
+    }
 }
 
-fn format_inline_pair(key: &str, value: &serde_json::Value) -> String {
+/// Entries of an inline object without aliases, as inside braces.
+fn inline_body(obj: &serde_json::Map<String, serde_json::Value>) -> String {
+    let mut entries = Vec::new();
+    InlineLayout::default().entries(obj, "", &mut entries);
+    entries.iter().map(InlineEntry::line).collect::<Vec<_>>().join(" ")
+}
+
+/// Chooses for each nested object of an inline document between a braced
+/// `key:{...}` entry and dotted `key.child` entries, whichever is shorter.
+/// Dotted entries repeat the whole path, so the choice depends on how long
+/// the path already is; a brace resets it, which keeps the number of
+/// lengths per object down to its depth.
+#[derive(Default)]
+struct InlineLayout {
+    /// Cost of an object's entries and whether they are dotted, keyed by
+    /// the object and the length of the path written before its key.
+    memo: HashMap<(usize, usize), (usize, bool)>,
+}
+
+impl InlineLayout {
+    /// Characters of the entries of `obj`, each with a separating space,
+    /// under a path of `prefix` characters.
+    fn cost(&mut self, obj: &serde_json::Map<String, serde_json::Value>, prefix: usize) -> usize {
+        obj.iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::Object(inner) if !inner.is_empty() => self.nested(key, inner, prefix).0,
+                _ => prefix + format_inline_pair(key, value).len() + 1,
+            })
+            .sum()
+    }
+
+    fn nested(&mut self, key: &str, inner: &serde_json::Map<String, serde_json::Value>, prefix: usize) -> (usize, bool) {
+        let id = inner as *const _ as usize;
+        if let Some(choice) = self.memo.get(&(id, prefix)) {
+            return *choice;
+        }
+        // `key:{` and `}` around the children, less one separating space.
+        let braced = prefix + key.len() + 3 + self.cost(inner, 0);
+        let dotted = self.cost(inner, prefix + key.len() + 1);
+        let choice = if dotted < braced { (dotted, true) } else { (braced, false) };
+        self.memo.insert((id, prefix), choice);
+        choice
+    }
+
+    fn entries(&mut self, obj: &serde_json::Map<String, serde_json::Value>, path: &str, out: &mut Vec<InlineEntry>) {
+        let prefix = if path.is_empty() { 0 } else { path.len() + 1 };
+        for (k, v) in obj {
+            let key = if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+            match v {
+                serde_json::Value::Object(inner) if !inner.is_empty() => {
+                    if self.nested(k, inner, prefix).1 {
+                        self.entries(inner, &key, out);
+                    } else {
+                        let mut children = Vec::new();
+                        self.entries(inner, "", &mut children);
+                        out.push(InlineEntry { key, value: InlineValue::Braced(children) });
+                    }
+                }
+                _ => out.push(InlineEntry::pair(key, v)),
+            }
+        }
+    }
+}
+
+/// Checks that an inline value survives a round trip through
+/// `encode_inline`/`decode_inline`, naming the offending path otherwise.
+/// Each leaf is decoded back from its own entry.
+#[cfg_attr(not(any(feature = "yaml", feature = "toml")), allow(dead_code))]
+pub(crate) fn check_inline(path: &str, value: &serde_json::Value) -> Result<()> {
+    let unsupported = |what: &str| Err(ZoonError::UnsupportedType(format!("{} at {}", what, path)));
     match value {
-        serde_json::Value::String(s) => format!("{}={}", key, s.replace(' ', "_")),
-        serde_json::Value::Bool(b) => format!("{}:{}", key, if *b { "y" } else { "n" }),
-        serde_json::Value::Number(n) => format!("{}:{}", key, n),
-        serde_json::Value::Null => format!("{}:~", key),
+        serde_json::Value::String(s) if s.contains(['\n', '\r']) => unsupported("multi-line string"),
         serde_json::Value::Object(obj) => {
-            let inner = encode_inline(obj).unwrap_or_default();
-            format!("{}:{{{}}}", key, inner)
+            for (k, v) in obj {
+                if k.is_empty() || k.contains(|c: char| c.is_whitespace() || ":={}[].".contains(c)) {
+                    return Err(ZoonError::UnsupportedType(format!("key {:?} at {}", k, path)));
+                }
+                let child = if path.is_empty() { k.clone() } else { format!("{}.{}", path, k) };
+                check_inline(&child, v)?;
+            }
+            Ok(())
         }
-        serde_json::Value::Array(_) => format!("{}:[...]", key),
+        _ => match crate::decode::decode_inline(&format_inline_pair("v", value)) {
+            Ok(decoded) if decoded.get("v") == Some(value) => Ok(()),
+            _ => unsupported("value without an inline form"),
+        },
     }
 }
 
-fn serialize_value(value: &serde_json::Value) -> String {
+fn format_inline_pair(key: &str, value: &serde_json::Value) -> String {
+    format!("{}{}", key, inline_value(value))
+}
+
+/// Separator and value of an inline entry.
+fn inline_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) if inline_needs_quotes(s) => format!("={}", quote_text(s)),
+        serde_json::Value::String(s) => format!("={}", s.replace(' ', "_")),
+        serde_json::Value::Bool(b) => format!(":{}", if *b { "y" } else { "n" }),
+        serde_json::Value::Number(n) => format!(":{}", format_number(n)),
+        serde_json::Value::Null => ":~".to_string(),
+        serde_json::Value::Object(obj) => format!(":{{{}}}", inline_body(obj)),
+        serde_json::Value::Array(items) => format!(":[{}]", serialize_items(items)),
+    }
+}
+
+/// Formats a single row cell for a column of the given type.
+pub(crate) fn format_cell(val: &serde_json::Value, type_code: &str) -> String {
+    if let serde_json::Value::String(raw) = val {
+        return format_str_cell(raw, type_code);
+    }
+    if let (serde_json::Value::Bool(b), "t") = (val, type_code) {
+        // Bare `t` cells are read by their token, where 1 is a number.
+        return if *b { "y".into() } else { "n".into() };
+    }
+    let mut s = serialize_value(val);
+    if let Some(options) = type_code.strip_prefix('!') {
+        // Numbers sharing an enum with strings are written by index too.
+        if let Some(idx) = options.split('|').position(|o| o == s) {
+            return idx.to_string();
+        }
+    }
+    if type_code == "b" {
+         if s == "true" { s = "1".into(); }
+         else if s == "false" { s = "0".into(); }
+    }
+    s
+}
+
+/// Formats a string cell, mapping it to its position for indexed enums.
+/// A hoisted prefix (`s+usr_`) is stripped and identifiers are compacted.
+pub(crate) fn format_str_cell(raw: &str, type_code: &str) -> String {
+    let (type_code, raw) = match type_code.split_once('+') {
+        Some((typ @ ("s" | "t" | "u" | "x"), prefix)) => (typ, raw.strip_prefix(prefix).unwrap_or(raw)),
+        _ => (type_code, raw),
+    };
+    if let Some(compact) = ident::compact(raw, type_code) {
+        return compact;
+    }
+    let s = raw.replace(' ', "_");
+    if let Some(options) = type_code.strip_prefix('!') {
+        if let Some(idx) = options.split('|').position(|o| o == s) {
+            return idx.to_string();
+        }
+    }
+    if type_code == "t" {
+        quote_text(raw)
+    } else if s.is_empty() {
+        // An empty token would shift every following cell.
+        "\"\"".into()
+    } else {
+        s
+    }
+}
+
+/// Writes a string in quotes, escaping `"` and `\`, and line breaks so the
+/// cell stays on its row.
+fn quote_text(raw: &str) -> String {
+    let escaped = raw.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\r', "\\r");
+    format!("\"{}\"", escaped)
+}
+
+/// Whether an inline string must be quoted: bare values end at whitespace,
+/// and quotes and brackets inside them would unbalance a braced entry.
+fn inline_needs_quotes(raw: &str) -> bool {
+    needs_quotes(raw) || raw.contains(['"', '[', ']', '{', '}'])
+}
+
+/// Whether a string must be quoted to read back unchanged: bare cells turn
+/// `_` into spaces, `~` and `*` stand for null and the default, and a
+/// leading quote or bracket opens a token. A cell of only `#` could start
+/// a header or section line, and whitespace other than a space would end
+/// the cell or its row.
+pub(crate) fn needs_quotes(raw: &str) -> bool {
+    raw.contains(|c: char| c == '_' || (c.is_whitespace() && c != ' ')) || opens_token(raw)
+}
+
+/// Whether a bare cell reads as something other than a string.
+fn opens_token(cell: &str) -> bool {
+    cell.starts_with(['"', '['])
+        || cell == "~"
+        || cell == DEFAULT_CELL
+        || (!cell.is_empty() && cell.trim_start_matches('#').is_empty())
+}
+
+pub(crate) fn serialize_value(value: &serde_json::Value) -> String {
     match value {
         serde_json::Value::String(s) => s.replace(' ', "_"),
-        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::Number(n) => format_number(n),
         serde_json::Value::Bool(b) => if *b { "1".into() } else { "0".into() },
         serde_json::Value::Null => "~".into(),
-        serde_json::Value::Object(obj) => {
-            let inner = encode_inline(obj).unwrap_or_default();
-            format!("{{{}}}", inner)
-        }
-        serde_json::Value::Array(_) => "[...]".into(),
+        serde_json::Value::Object(obj) => format!("{{{}}}", inline_body(obj)),
+        serde_json::Value::Array(items) => format!("[{}]", serialize_items(items)),
+    }
+}
+
+/// Spec 3.2: numbers are written without exponential notation.
+fn format_number(n: &serde_json::Number) -> String {
+    let s = n.to_string();
+    match n.as_f64() {
+        Some(f) if s.contains(['e', 'E']) => f.to_string(),
+        _ => s,
     }
 }
 
+fn serialize_items(items: &[serde_json::Value]) -> String {
+    let parts: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            serde_json::Value::Bool(b) => if *b { "y".into() } else { "n".into() },
+            // Items that would not read back bare are written as JSON.
+            // Compact JSON has spaces only inside strings, and escaping
+            // them keeps the cell a single token.
+            serde_json::Value::String(s) => bare_item(s).unwrap_or_else(|| item.to_string().replace(' ', "\\u0020")),
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => item.to_string().replace(' ', "\\u0020"),
+            other => serialize_value(other),
+        })
+        .collect();
+    parts.join(",")
+}
+
+/// A string array item as a bare token, if it reads back as the same
+/// string: no delimiters or quotes, and not a number, boolean or `~`.
+fn bare_item(s: &str) -> Option<String> {
+    let bare = s.replace(' ', "_");
+    let plain = !s.contains('_')
+        && !bare.is_empty()
+        && !bare.contains(|c: char| c.is_whitespace() || ",[]{}\"".contains(c));
+    (plain && crate::decode::parse_value(&bare, "auto") == serde_json::Value::String(s.to_string())).then_some(bare)
+}
+
 #[derive(Default)]
 struct ColumnStats {
     values: Vec<String>,
+    /// Cells as they would be written in a `t` column.
+    quoted: Vec<String>,
     unique_vals: std::collections::HashSet<String>,
-    is_seq: bool,
-    indexed: bool,
-    enum_keys: Vec<String>,
-    is_text: bool,
+    has_arrays: bool,
+    has_bools: bool,
+    has_scalars: bool,
+    has_strings: bool,
+    has_numbers: bool,
+    needs_quotes: bool,
+    /// Whether strings need quotes even with the hoistable prefix removed.
+    rest_needs_quotes: bool,
+    /// Longest common prefix of the string values.
+    common_prefix: Option<String>,
 }
 
-fn infer_type(stat: &ColumnStats, arr_len: usize, key: &str) -> String {
-    if key.to_lowercase() == "id" && stat.is_seq && check_sequence(&stat.values) {
-        return "i+".into();
+fn column_stats(rows: &[BTreeMap<String, serde_json::Value>], key: &str) -> ColumnStats {
+    let mut stat = ColumnStats::default();
+    for row in rows {
+        let val = row.get(key).unwrap_or(&serde_json::Value::Null);
+        let s = serialize_value(val);
+
+        stat.quoted.push(format_cell(val, "t"));
+        stat.values.push(s.clone());
+        stat.unique_vals.insert(s);
+        match val {
+            serde_json::Value::Array(_) => stat.has_arrays = true,
+            serde_json::Value::Bool(_) => stat.has_bools = true,
+            serde_json::Value::Null => {}
+            serde_json::Value::String(raw) => {
+                stat.has_scalars = true;
+                stat.has_strings = true;
+                stat.needs_quotes |= needs_quotes(raw);
+                stat.common_prefix = Some(match stat.common_prefix.take() {
+                    Some(prefix) => common_prefix(&prefix, raw).to_string(),
+                    None => raw.clone(),
+                });
+            }
+            _ => {
+                stat.has_scalars = true;
+                stat.has_numbers = true;
+            }
+        }
+    }
+    if stat.needs_quotes {
+        let prefix = hoistable_prefix(&stat).to_string();
+        stat.rest_needs_quotes = rows.iter().any(|row| match row.get(key) {
+            Some(serde_json::Value::String(raw)) => needs_quotes(raw.strip_prefix(prefix.as_str()).unwrap_or(raw)),
+            _ => false,
+        });
+    }
+    stat
+}
+
+fn infer_type(stat: &ColumnStats, arr_len: usize, key: &str, cost: Option<&dyn TokenCost>) -> String {
+    // Strings mixed with other values go in a `t` column, where they are
+    // quoted and everything else reads back by its token.
+    let kinds = [stat.has_strings, stat.has_numbers, stat.has_bools, stat.has_arrays];
+    if kinds.iter().filter(|kind| **kind).count() > 1 {
+        return "t".into();
+    }
+
+    let numeric = stat.has_scalars && !stat.has_strings && !stat.has_bools && !stat.has_arrays;
+    if numeric {
+        if let Some(type_code) = sequence_type(key, &stat.values, cost) {
+            return type_code;
+        }
     }
 
-    let all_nums = stat.values.iter().all(|v| v.parse::<i64>().is_ok() || v == "~");
-    if all_nums && !stat.values.iter().all(|v| v == "~") {
-        return "i".into();
+    if stat.has_arrays && !stat.has_bools && !stat.has_scalars {
+        return "a".into();
     }
 
-    let all_bools = stat.values.iter().all(|v| v == "0" || v == "1" || v == "~");
-    if all_bools {
+    if stat.has_bools && !stat.has_arrays && !stat.has_scalars {
         return "b".into();
     }
 
-    if stat.unique_vals.len() <= 10 && stat.unique_vals.len() < arr_len {
+    if !stat.has_strings {
+        let all_nums = stat.values.iter().all(|v| v.parse::<i64>().is_ok() || v == "~");
+        if all_nums && !stat.values.iter().all(|v| v == "~") {
+            return "i".into();
+        }
+
+        // Decimals share the numeric column type, as in the reference encoder.
+        let all_decimals = stat.has_scalars && !stat.has_bools && !stat.has_arrays
+            && stat.values.iter().all(|v| crate::decode::is_decimal(v) || v == "~");
+        if all_decimals {
+            return "i".into();
+        }
+
+        let all_bools = stat.values.iter().all(|v| v == "0" || v == "1" || v == "~");
+        if all_bools {
+            return "b".into();
+        }
+    }
+
+    if stat.has_strings && !stat.has_numbers && !stat.has_bools && !stat.has_arrays && !stat.rest_needs_quotes {
+        if let Some(type_code) = temporal_type(&stat.values, cost) {
+            return type_code;
+        }
+        if let Some(type_code) = identifier_type(stat, cost) {
+            return type_code;
+        }
+    }
+
+    if stat.needs_quotes {
+        // A hoisted prefix may hold everything that needed quoting, which
+        // saves the quotes when it costs less than them.
+        if !stat.rest_needs_quotes {
+            let prefix = hoistable_prefix(stat);
+            let measure = |text: &str| cost.map_or(text.len(), |c| c.cost(text));
+            let rests: usize = stat
+                .values
+                .iter()
+                .map(|v| match v.as_str() {
+                    "~" => measure(" ~"),
+                    v if v.len() == prefix.len() => measure(" \"\""),
+                    v => measure(&format!(" {}", &v[prefix.len()..])),
+                })
+                .sum();
+            let cells: usize = stat.quoted.iter().map(|c| measure(&format!(" {}", c))).sum();
+            let hoisted = measure(&format!(" {}:s+{}", key, prefix)) + rests;
+            let quoted = measure(&format!(" {}:t", key)) + cells;
+            if hoisted <= quoted {
+                return format!("s+{}", prefix);
+            }
+        }
+        return "t".into();
+    }
+
+    // Enum options are separated by `|`, so values holding one stay cells.
+    let separable = stat.unique_vals.iter().all(|v| !v.contains('|'));
+    if separable && stat.unique_vals.len() <= 10 && stat.unique_vals.len() < arr_len {
         let mut vals: Vec<_> = stat.unique_vals.iter().filter(|v| *v != "~").cloned().collect();
         vals.sort();
         if !vals.is_empty() {
+            if let Some(cost) = cost {
+                let literal = format!("={}", vals.join("|"));
+                let indexed = format!("!{}", vals.join("|"));
+                let index_of = |v: &String| vals.iter().position(|o| o == v).map_or(v.clone(), |i| i.to_string());
+                let literal_cost = cost.cost(&format!(" {}{}", key, literal)) + cells_cost(cost, stat.values.iter().cloned());
+                let index_cost = cost.cost(&format!(" {}{}", key, indexed)) + cells_cost(cost, stat.values.iter().map(index_of));
+                return if index_cost < literal_cost { indexed } else { literal };
+            }
             if vals.len() >= 3 {
                 let avg_len: usize = vals.iter().map(|v| v.len()).sum::<usize>() / vals.len();
                 let literal_cost = avg_len * arr_len;
@@ -375,6 +1278,12 @@ fn infer_type(stat: &ColumnStats, arr_len: usize, key: &str) -> String {
         }
     }
 
+    if let Some(cost) = cost {
+        let plain = cells_cost(cost, stat.values.iter().cloned());
+        let quoted = cells_cost(cost, stat.quoted.iter().cloned());
+        return if quoted < plain { "t".into() } else { "s".into() };
+    }
+
     let total_len: usize = stat.values.iter().map(|v| v.len()).sum();
     if !stat.values.is_empty() && total_len / stat.values.len() > 30 {
         return "t".into();
@@ -383,6 +1292,94 @@ fn infer_type(stat: &ColumnStats, arr_len: usize, key: &str) -> String {
     "s".into()
 }
 
+/// Tokens of a column's cells, each with the space that separates it from
+/// the previous cell.
+fn cells_cost(cost: &dyn TokenCost, cells: impl Iterator<Item = String>) -> usize {
+    cells.map(|c| cost.cost(&format!(" {}", c))).sum()
+}
+
+/// Type code of a column indexing into the document dictionary.
+const DICTIONARY_TYPE: &str = "$";
+
+/// Whether a column's values can be dictionary entries: plain `s` cells
+/// that read back as strings. Enum columns already index their values.
+fn dictionary_candidate(type_code: &str, stat: &ColumnStats) -> bool {
+    type_code == "s"
+        && stat.has_strings
+        && !(stat.has_numbers || stat.has_bools || stat.has_arrays || stat.needs_quotes)
+        && stat.unique_vals.iter().all(|v| !v.is_empty() && !v.contains(char::is_whitespace))
+}
+
+/// Entries shared by `columns`, most frequent first so the common values
+/// get the shortest indices.
+fn dictionary_entries(columns: &[&ColumnStats]) -> Vec<String> {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for value in columns.iter().flat_map(|stat| &stat.values).filter(|v| *v != "~") {
+        let next = counts.len();
+        counts.entry(value).or_insert((0, next)).0 += 1;
+    }
+    let mut entries: Vec<(&str, (usize, usize))> = counts.into_iter().collect();
+    entries.sort_by_key(|(_, (count, first))| (std::cmp::Reverse(*count), *first));
+    entries.into_iter().map(|(entry, _)| entry.to_string()).collect()
+}
+
+/// Moves string columns whose values repeat into a document dictionary
+/// (`$` line) when its indices and the line cost less than the literal
+/// values. Columns join one at a time, best first, and stay only if the
+/// shared dictionary still pays off. Returns the entries, empty if unused.
+fn choose_dictionary(type_codes: &mut [String], stats: &[ColumnStats], cost: Option<&dyn TokenCost>) -> Vec<String> {
+    // A cell with the space before it.
+    let cell = |text: &str| cost.map_or(text.len() + 1, |c| c.cost(&format!(" {}", text)));
+    let eligible: Vec<bool> = type_codes
+        .iter()
+        .zip(stats)
+        .map(|(code, stat)| dictionary_candidate(code, stat) && stat.unique_vals.len() < stat.values.len())
+        .collect();
+    let literal: Vec<usize> = stats
+        .iter()
+        .zip(&eligible)
+        .map(|(stat, eligible)| if *eligible { stat.values.iter().map(|v| cell(v)).sum() } else { 0 })
+        .collect();
+    // Literal cells of `chosen` against the dictionary line plus indices.
+    let savings = |chosen: &[usize]| -> isize {
+        let columns: Vec<&ColumnStats> = chosen.iter().map(|i| &stats[*i]).collect();
+        let entries = dictionary_entries(&columns);
+        let index: HashMap<&str, usize> = entries.iter().enumerate().map(|(i, e)| (e.as_str(), i)).collect();
+        let cells: usize = columns
+            .iter()
+            .flat_map(|stat| &stat.values)
+            .map(|v| index.get(v.as_str()).map_or_else(|| cell(v), |i| cell(&i.to_string())))
+            .sum();
+        let line = format!("$ {}\n", entries.join(" "));
+        let dictionary = cost.map_or(line.len(), |c| c.cost(&line)) + cells;
+        chosen.iter().map(|i| literal[*i]).sum::<usize>() as isize - dictionary as isize
+    };
+
+    let mut candidates: Vec<(usize, isize)> = (0..type_codes.len())
+        .filter(|i| eligible[*i])
+        .map(|i| (i, savings(&[i])))
+        .collect();
+    candidates.sort_by_key(|(i, saved)| (std::cmp::Reverse(*saved), *i));
+
+    let mut chosen: Vec<usize> = Vec::new();
+    let mut best = 0;
+    for (i, _) in candidates {
+        chosen.push(i);
+        let saved = savings(&chosen);
+        if saved > best {
+            best = saved;
+        } else {
+            chosen.pop();
+        }
+    }
+    chosen.sort_unstable();
+    for i in &chosen {
+        type_codes[*i] = DICTIONARY_TYPE.to_string();
+    }
+    let columns: Vec<&ColumnStats> = chosen.iter().map(|i| &stats[*i]).collect();
+    dictionary_entries(&columns)
+}
+
 fn check_sequence(values: &[String]) -> bool {
     for (i, v) in values.iter().enumerate() {
         if v != &(i + 1).to_string() {
@@ -391,3 +1388,211 @@ fn check_sequence(values: &[String]) -> bool {
     }
     true
 }
+
+/// Picks `i+` for an integer column that steps by a fixed amount (with
+/// `start,step` unless it is `1,1`), or `i^` for a monotonic one whose
+/// differences are cheaper than its values. An `id` column is `i+` from
+/// one row; other columns need three, since any two values are a sequence.
+pub(crate) fn sequence_type(key: &str, cells: &[String], cost: Option<&dyn TokenCost>) -> Option<String> {
+    if key.to_lowercase() == "id" && check_sequence(cells) {
+        return Some("i+".into());
+    }
+    if cells.len() < 3 {
+        return None;
+    }
+    let values: Vec<i64> = cells.iter().map(|c| c.parse().ok()).collect::<Option<_>>()?;
+    let steps: Vec<i64> = values.windows(2).map(|w| w[1].checked_sub(w[0])).collect::<Option<_>>()?;
+
+    let step = steps[0];
+    if steps.iter().all(|s| *s == step) {
+        return Some(match (values[0], step) {
+            (1, 1) => "i+".into(),
+            (start, 1) => format!("i+{}", start),
+            (start, step) => format!("i+{},{}", start, step),
+        });
+    }
+
+    let monotonic = steps.iter().all(|s| *s >= 0) || steps.iter().all(|s| *s <= 0);
+    if !monotonic {
+        return None;
+    }
+    let deltas = delta_cells(cells);
+    let cheaper = match cost {
+        Some(cost) => cells_cost(cost, deltas.into_iter()) < cells_cost(cost, cells.iter().cloned()),
+        None => deltas.iter().map(String::len).sum::<usize>() < cells.iter().map(String::len).sum::<usize>(),
+    };
+    cheaper.then(|| "i^".into())
+}
+
+fn common_prefix<'a>(a: &'a str, b: &str) -> &'a str {
+    let len = a.char_indices().zip(b.chars()).find(|((_, x), y)| x != y).map_or(a.len().min(b.len()), |((i, _), _)| i);
+    &a[..len]
+}
+
+/// The part of a column's common prefix worth hoisting into the header:
+/// cut back to a separator (`usr_`, `ORD-2025-`) or to where letters turn
+/// into digits (`ORD` of `ORD1001`), so it stays readable. Empty when there
+/// is nothing to share or it is only punctuation.
+fn hoistable_prefix(stat: &ColumnStats) -> &str {
+    let Some(prefix) = stat.common_prefix.as_deref() else { return "" };
+    let digits = prefix.ends_with(|c: char| c.is_ascii_digit());
+    let prefix = prefix.trim_end_matches(|c: char| c.is_alphanumeric() && c.is_ascii_digit() == digits);
+    let shared = stat.values.iter().filter(|v| *v != "~").count();
+    // `@` would start a default (`s@value`).
+    if prefix.chars().count() < 2 || !prefix.contains(char::is_alphanumeric) || prefix.contains(|c: char| c.is_whitespace() || c == '@') || shared < 2 {
+        return "";
+    }
+    prefix
+}
+
+/// Gives a column where one value fills three in four cells or more that
+/// value as its default (`status:s@healthy`), so those rows hold `*`, when
+/// that is cheaper. Literal enums become `s` columns to do so.
+fn with_default(type_code: String, stat: &ColumnStats, key: &str, cost: Option<&dyn TokenCost>) -> String {
+    let plain = match type_code.as_str() {
+        "s" | "i" | "b" => type_code.as_str(),
+        code if code.starts_with('=') => "s",
+        _ => return type_code,
+    };
+    // Count and first row of each value; ties go to the value seen first,
+    // so the output does not depend on hash order.
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for (i, value) in stat.values.iter().enumerate().filter(|(_, v)| *v != "~") {
+        counts.entry(value).or_insert((0, i)).0 += 1;
+    }
+    let Some((default, (count, _))) = counts.iter().max_by_key(|(_, (count, first))| (*count, std::cmp::Reverse(*first))) else {
+        return type_code;
+    };
+    let default = *default;
+    let non_null: usize = counts.values().map(|(n, _)| n).sum();
+    if *count < 2 || *count * 4 < non_null * 3 || counts.contains_key(DEFAULT_CELL) || stat.needs_quotes {
+        return type_code;
+    }
+
+    let header = |code: &str| if code.starts_with('=') { format!(" {}{}", key, code) } else { format!(" {}:{}", key, code) };
+    let with_default = format!("{}@{}", plain, default);
+    let defaulted = || stat.values.iter().map(|v| if v == default { DEFAULT_CELL.to_string() } else { v.clone() });
+    let cheaper = match cost {
+        Some(cost) => cost.cost(&header(&with_default)) + cells_cost(cost, defaulted()) < cost.cost(&header(&type_code)) + cells_cost(cost, stat.values.iter().cloned()),
+        None => header(&with_default).len() + defaulted().map(|c| c.len()).sum::<usize>() < header(&type_code).len() + stat.values.iter().map(String::len).sum::<usize>(),
+    };
+    if cheaper {
+        with_default
+    } else {
+        type_code
+    }
+}
+
+/// Adds a hoisted prefix to an `s` or `t` column when that is cheaper.
+fn with_prefix(type_code: String, stat: &ColumnStats, cost: Option<&dyn TokenCost>) -> String {
+    if (type_code != "s" && type_code != "t") || stat.has_numbers || stat.has_bools || stat.has_arrays {
+        return type_code;
+    }
+    let prefix = hoistable_prefix(stat);
+    if prefix.is_empty() {
+        return type_code;
+    }
+    let cells = || stat.values.iter().filter(|v| *v != "~");
+    // What is left must still read as an `s` cell.
+    let rest = |v: &String| v[prefix.len()..].to_string();
+    if type_code == "s" && cells().map(rest).any(|r| opens_token(&r)) {
+        return type_code;
+    }
+    let cheaper = match cost {
+        Some(cost) => cost.cost(&format!("+{}", prefix)) + cells_cost(cost, cells().map(rest)) < cells_cost(cost, cells().cloned()),
+        None => true,
+    };
+    if cheaper {
+        format!("{}+{}", type_code, prefix)
+    } else {
+        type_code
+    }
+}
+
+/// Picks `u` for a column of lowercase UUIDs and `x` for one of long
+/// lowercase hex strings, after a hoisted prefix if they share one.
+fn identifier_type(stat: &ColumnStats, cost: Option<&dyn TokenCost>) -> Option<String> {
+    // Random identifiers can share a few leading characters by chance.
+    let prefixes = [hoistable_prefix(stat), ""];
+    prefixes.iter().find_map(|prefix| {
+        let rests: Vec<&str> = stat.values.iter().filter(|v| *v != "~").map(|v| &v[prefix.len()..]).collect();
+        let typ = if rests.iter().all(|r| ident::is_uuid(r)) {
+            "u"
+        } else if rests.iter().all(|r| ident::is_hex_id(r)) {
+            let compact = rests.iter().filter_map(|r| ident::compact(r, "x"));
+            if cost.is_some_and(|cost| cells_cost(cost, compact) >= cells_cost(cost, rests.iter().map(|r| r.to_string()))) {
+                return None;
+            }
+            "x"
+        } else {
+            return None;
+        };
+        Some(if prefix.is_empty() { typ.to_string() } else { format!("{}+{}", typ, prefix) })
+    })
+}
+
+/// Picks `du`, `d` or `dt` for a column of durations, dates or datetimes
+/// that format back to the same text. Dates, and datetimes sharing a zone
+/// and without fractional seconds, are written as offsets from the first
+/// value when that is cheaper.
+fn temporal_type(cells: &[String], cost: Option<&dyn TokenCost>) -> Option<String> {
+    let values: Vec<&str> = cells.iter().map(String::as_str).filter(|c| *c != "~").collect();
+    let first = *values.first()?;
+    if values.iter().all(|v| temporal::parse_duration(v).is_some_and(|s| temporal::format_duration(s) == *v)) {
+        return Some("du".into());
+    }
+    let code = if values.iter().all(|v| temporal::parse_date(v).is_some()) {
+        "d"
+    } else {
+        let datetimes: Vec<_> = values.iter().map(|v| temporal::parse_datetime(v)).collect::<Option<_>>()?;
+        let zone = datetimes[0].zone;
+        if datetimes.iter().any(|dt| !dt.fraction.is_empty() || dt.zone != zone) {
+            // Offsets would drop the fractions and zones.
+            return Some("dt".into());
+        }
+        "dt"
+    };
+
+    let based = format!("{}{}", code, first);
+    let offsets = temporal_cells(&based, cells);
+    let cheaper = match cost {
+        Some(cost) => cost.cost(&based) + cells_cost(cost, offsets.into_iter()) < cost.cost(code) + cells_cost(cost, cells.iter().cloned()),
+        None => based.len() + offsets.iter().map(String::len).sum::<usize>() < code.len() + cells.iter().map(String::len).sum::<usize>(),
+    };
+    Some(if cheaper { based } else { code.into() })
+}
+
+/// Cells of a `d`, `dt` or `du` column: seconds for durations, offsets
+/// from the base for based columns, else the values themselves.
+fn temporal_cells(type_code: &str, cells: &[String]) -> Vec<String> {
+    let (typ, base) = temporal::split_base(type_code, "").unwrap_or_else(|_| (type_code.to_string(), None));
+    cells
+        .iter()
+        .map(|c| {
+            let number = match (typ.as_str(), &base) {
+                ("du", _) => temporal::parse_duration(c),
+                ("d", Some((days, _))) => temporal::parse_date(c).map(|d| d - days),
+                ("dt", Some((seconds, _))) => temporal::parse_datetime(c).map(|dt| dt.seconds - seconds),
+                _ => None,
+            };
+            number.map_or_else(|| c.clone(), |n| n.to_string())
+        })
+        .collect()
+}
+
+/// Cells of a delta (`i^`) column: the first value, then the difference
+/// from the previous one. Nulls stay `~`.
+pub(crate) fn delta_cells(cells: &[String]) -> Vec<String> {
+    let mut previous = 0i64;
+    cells
+        .iter()
+        .map(|c| match c.parse::<i64>() {
+            Ok(value) => {
+                let delta = value - previous;
+                previous = value;
+                delta.to_string()
+            }
+            Err(_) => c.clone(),
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+//! Compact identifiers for `u` and `x` columns (SPEC §4.11).
+//!
+//! UUIDs and lowercase hex strings are written as unpadded base64url of
+//! their bytes: 22 characters for a UUID instead of 36.
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    out
+}
+
+/// Decodes unpadded base64url, rejecting any text that [`to_base64`]
+/// would not produce.
+fn from_base64(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let digit = ALPHABET.iter().position(|a| a == c)? as u32;
+            n |= digit << (18 - 6 * i);
+        }
+        let len = chunk.len() - 1;
+        if n & ((1 << (24 - 8 * len)) - 1) != 0 {
+            return None;
+        }
+        out.extend((0..len).map(|i| (n >> (16 - 8 * i)) as u8));
+    }
+    Some(out)
+}
+
+/// Bytes of lowercase hex text with an even number of digits.
+fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) || !s.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        return None;
+    }
+    (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).ok()).collect()
+}
+
+fn format_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Whether `s` is a lowercase hyphenated UUID.
+pub(crate) fn is_uuid(s: &str) -> bool {
+    let b = s.as_bytes();
+    b.len() == 36
+        && [8, 13, 18, 23].iter().all(|i| b[*i] == b'-')
+        && s.split('-').all(|group| parse_hex(group).is_some())
+}
+
+/// Whether `s` is lowercase hex long enough (16 digits) not to be a
+/// number that happens to look like hex.
+pub(crate) fn is_hex_id(s: &str) -> bool {
+    s.len() >= 16 && parse_hex(s).is_some()
+}
+
+/// Compact cell of a UUID or hex identifier.
+pub(crate) fn compact(s: &str, typ: &str) -> Option<String> {
+    match typ {
+        "u" if is_uuid(s) => parse_hex(&s.replace('-', "")).map(|bytes| to_base64(&bytes)),
+        "x" => parse_hex(s).map(|bytes| to_base64(&bytes)),
+        _ => None,
+    }
+}
+
+/// Text of a compact `u` or `x` cell.
+pub(crate) fn expand(token: &str, typ: &str) -> Option<String> {
+    let bytes = from_base64(token)?;
+    match typ {
+        "u" if bytes.len() == 16 => {
+            let hex = format_hex(&bytes);
+            Some(format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..]))
+        }
+        "x" if !bytes.is_empty() => Some(format_hex(&bytes)),
+        _ => None,
+    }
+}
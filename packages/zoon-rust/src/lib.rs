@@ -1,14 +1,41 @@
 mod encode;
 mod decode;
+mod ident;
+mod metadata;
+mod temporal;
+pub mod bpe;
+pub mod ndjson;
+pub mod stats;
+pub mod toon;
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "yaml")]
+pub mod yaml;
+#[cfg(feature = "toml")]
+pub mod toml;
+#[cfg(feature = "tokio")]
+pub mod tokio;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "chrono")]
+pub mod chrono;
+#[cfg(feature = "time")]
+pub mod time;
 
-pub use encode::encode;
-pub use decode::decode;
+pub use encode::{encode, encode_sections, encode_sections_with_options, encode_with_options, EncodeOptions, Pretty, Reorder, TokenCost};
+pub use decode::{decode, decode_with_options, DecodeOptions};
+pub use metadata::{read_metadata, Metadata};
+pub use bpe::Vocabulary;
+pub use ndjson::{ndjson_to_zoon, zoon_to_ndjson, NdjsonOptions, SchemaFallback};
 
 #[derive(Debug, PartialEq)]
 pub enum ZoonError {
     InvalidFormat(String),
     UnsupportedType(String),
     ParseError(String),
+    Io(String),
+    /// A `DecodeOptions` limit was hit.
+    LimitExceeded(String),
 }
 
 impl std::fmt::Display for ZoonError {
@@ -17,12 +44,20 @@ impl std::fmt::Display for ZoonError {
             ZoonError::InvalidFormat(s) => write!(f, "invalid format: {}", s),
             ZoonError::UnsupportedType(s) => write!(f, "unsupported type: {}", s),
             ZoonError::ParseError(s) => write!(f, "parse error: {}", s),
+            ZoonError::Io(s) => write!(f, "io error: {}", s),
+            ZoonError::LimitExceeded(s) => write!(f, "limit exceeded: {}", s),
         }
     }
 }
 
 impl std::error::Error for ZoonError {}
 
+impl From<std::io::Error> for ZoonError {
+    fn from(e: std::io::Error) -> Self {
+        ZoonError::Io(e.to_string())
+    }
+}
+
 pub type Result<T> = std::result::Result<T, ZoonError>;
 
 #[cfg(test)]
@@ -68,6 +103,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_decode_tabular() {
         let input = "# id:i name:s role:s active:b\n1 Alice Admin 1\n2 Bob User 0";
         let users: Vec<User> = decode(input).unwrap();
@@ -128,7 +164,70 @@ mod tests {
         data.insert("b".to_string(), inner_b);
 
         let encoded = encode(&data).unwrap();
-        assert!(encoded.contains("a:{x:1}"));
+        assert!(encoded.contains("a.x:1"));
+        assert!(encoded.contains("b.y:2"));
+    }
+
+    #[test]
+    fn test_inline_paths() {
+        // A lone child is shorter dotted, several share one pair of braces.
+        let data = serde_json::json!({
+            "server": {"tls": {"cert": "server.pem"}},
+            "db": {"host": "db.internal", "port": 5432, "user": "app"},
+        });
+        let encoded = encode(&data).unwrap();
+        assert_eq!(encoded, "server.tls.cert=server.pem db:{host=db.internal port:5432 user=app}");
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+
+        // Aliased keys expand through the alias lines before the entries,
+        // braced or dotted, and definitions may build on earlier ones.
+        let input = "// staging\n%s=services %a=%s.api\n%a.host=api.internal %a.port:80 %s.cache:{ttl:60} region=eu";
+        let decoded: serde_json::Value = decode(input).unwrap();
+        assert_eq!(
+            decoded,
+            serde_json::json!({
+                "services": {"api": {"host": "api.internal", "port": 80}, "cache": {"ttl": 60}},
+                "region": "eu",
+            })
+        );
+    }
+
+    #[test]
+    fn test_pretty_inline() {
+        let data = serde_json::json!({
+            "name": "My App",
+            "scripts": {"dev": "vite", "env": {"node": 20, "ci": true}},
+            "deps": {"react": "^18.0.0"},
+            "tags": ["web", "api"],
+        });
+
+        let top = EncodeOptions { pretty: Pretty::TopLevel, ..Default::default() };
+        let encoded = encode_with_options(&data, &top).unwrap();
+        assert_eq!(encoded, "name=My_App\nscripts:{dev=vite env:{node:20 ci:y}}\ndeps.react=^18.0.0\ntags:[web,api]");
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+
+        let nested = EncodeOptions { pretty: Pretty::Nested, ..Default::default() };
+        let encoded = encode_with_options(&data, &nested).unwrap();
+        assert_eq!(
+            encoded,
+            "name=My_App\nscripts:{\n  dev=vite\n  env:{\n    node:20\n    ci:y\n  }\n}\ndeps.react=^18.0.0\ntags:[web,api]"
+        );
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+
+        // The layout is the one-line layout with line breaks added.
+        let compact = encode(&data).unwrap();
+        let words = |s: &str| s.split_whitespace().map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(words(&encoded).concat(), words(&compact).concat());
+
+        // Spaces in keys and quoted values stay on their line.
+        let spaced = serde_json::json!({"a b": {"c d": 1, "e": 2, "f": "g_h i"}});
+        let encoded = encode_with_options(&spaced, &nested).unwrap();
+        assert_eq!(encoded, "a b:{\n  c d:1\n  e:2\n  f=\"g_h i\"\n}");
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, spaced);
     }
 
     #[test]
@@ -142,6 +241,9 @@ mod tests {
         let encoded = encode(&data).unwrap();
 
         let decoded: Vec<Data> = decode(&encoded).unwrap();
+        assert_eq!(decoded[0].text, "Hello_World");
+
+        let decoded: Vec<Data> = decode("# text:s\nHello_World").unwrap();
         assert_eq!(decoded[0].text, "Hello World");
     }
 
@@ -179,8 +281,10 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_decode_with_booleans() {
         let input = "# name:s active:b\nAlice 1\nBob 0";
+        #[allow(dead_code)]
         #[derive(Deserialize, Debug)]
         struct Row {
             name: String,
@@ -194,6 +298,7 @@ mod tests {
     #[test]
     fn test_decode_numbers() {
         let input = "# name:s price:i\nWidget 1999\nGadget 2950";
+        #[allow(dead_code)]
         #[derive(Deserialize, Debug)]
         struct Product {
             name: String,
@@ -310,6 +415,33 @@ mod tests {
         assert_eq!(decoded[1].infrastructure.redis.state, "down");
     }
 
+    #[test]
+    fn test_alias_allocation() {
+        // Fourteen prefixes, more than the old cap of ten; two share an initial.
+        let prefixes = [
+            "alpha", "bravo", "charlie", "delta", "epsilon", "foxtrot", "gamma", "hotel", "india", "juliett", "kappa", "lambda",
+            "sierra", "station",
+        ];
+        let data: Vec<serde_json::Value> = (0..3)
+            .map(|i| {
+                let fields = prefixes.iter().map(|p| (p.to_string(), serde_json::json!({"reads": i * 7 % 5, "writes": i * 3 % 4, "errors": i % 2, "ms": i * 5 % 3})));
+                serde_json::Value::Object(fields.collect())
+            })
+            .collect();
+        let encoded = encode(&data).unwrap();
+        let alias_line = encoded.lines().next().unwrap();
+        assert_eq!(alias_line.split(' ').count(), 14);
+        assert!(alias_line.starts_with("%a=alpha %b=bravo "));
+        assert!(alias_line.ends_with(" %s=sierra %st=station"));
+        assert_eq!(encode(&data).unwrap(), encoded);
+        assert_eq!(decode::<Vec<serde_json::Value>>(&encoded).unwrap(), data);
+
+        // Aliases defined through earlier ones.
+        let nested = "%c=cluster %cn=%c.nodes %cnp=%cn.primary\n# %c.name:s %cnp.cpu:i %cn.standby.cpu:i\neu 41 3";
+        let decoded: serde_json::Value = decode(nested).unwrap();
+        assert_eq!(decoded, serde_json::json!([{"cluster": {"name": "eu", "nodes": {"primary": {"cpu": 41}, "standby": {"cpu": 3}}}}]));
+    }
+
     #[test]
     fn test_constants() {
         #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -351,4 +483,588 @@ mod tests {
         assert_eq!(decoded[2].id, 3);
         assert_eq!(decoded[0].status, "ok");
     }
+
+    #[test]
+    fn test_indexed_enum_roundtrip() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Order {
+            no: i32,
+            status: String,
+        }
+        let statuses = ["processing", "delivered", "cancelled"];
+        let data: Vec<Order> = (0..6).map(|i| Order { no: i * 7, status: statuses[i as usize % 3].into() }).collect();
+
+        let encoded = encode(&data).unwrap();
+        assert!(encoded.contains("no:i+0,7 status!cancelled|delivered|processing"));
+        assert!(encoded.contains("\n1\n"));
+
+        let decoded: Vec<Order> = decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+
+        let err = decode::<Vec<Order>>("# no:i status!a|b\n1 5").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_decode_limits() {
+        let limited = |err: ZoonError| matches!(err, ZoonError::LimitExceeded(_));
+
+        let deep = format!("{}1{}", "a:{".repeat(1_000), "}".repeat(1_000));
+        assert!(limited(decode::<serde_json::Value>(&deep).unwrap_err()));
+        let dotted = format!("# {}:i\n1", vec!["a"; 100].join("."));
+        assert!(limited(decode::<serde_json::Value>(&dotted).unwrap_err()));
+
+        assert!(limited(decode::<serde_json::Value>("# id:i+ +18446744073709551615").unwrap_err()));
+        // A count too large to parse is over the limit as well, not ignored.
+        assert!(limited(decode::<serde_json::Value>("# id:i+ +99999999999999999999").unwrap_err()));
+        let err = decode::<serde_json::Value>("# id:i+ +3x").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(_)));
+
+        let options = DecodeOptions { max_rows: 2, max_fields: 2, max_line_length: 16, ..Default::default() };
+        assert!(limited(decode_with_options::<serde_json::Value>("# a:i\n1\n2\n3", &options).unwrap_err()));
+        assert!(limited(decode_with_options::<serde_json::Value>("# a:i b:i c:i", &options).unwrap_err()));
+        assert!(limited(decode_with_options::<serde_json::Value>("# a:s\naaaaaaaaaaaaaaaaa", &options).unwrap_err()));
+        let options = DecodeOptions { max_total_bytes: 4, ..Default::default() };
+        assert!(limited(decode_with_options::<serde_json::Value>("a:1 b:2", &options).unwrap_err()));
+        assert!(decode_with_options::<serde_json::Value>("a:1", &options).is_ok());
+    }
+
+    #[test]
+    fn test_decode_malformed_rows() {
+        for input in ["#", "# a:s\n\"", "# a:s\n\"abc", "# a:a\n[1,2", "# éé:s\nx", "%\n#", "a:{b:{"] {
+            let _ = decode::<serde_json::Value>(input);
+        }
+        let err = decode::<serde_json::Value>("# a:s\n\"abc").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(_)));
+    }
+
+    #[test]
+    fn test_large_table_order() {
+        // Big enough to be split into parallel chunks with the rayon feature.
+        let rows: Vec<serde_json::Value> = (1..=5000)
+            .map(|i| serde_json::json!({"id": i, "name": format!("user{}", i), "score": i % 7}))
+            .collect();
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.starts_with("# id:i+ name:s score:i\n"));
+        let decoded: Vec<serde_json::Value> = decode(&encoded).unwrap();
+        assert_eq!(decoded, rows);
+
+        let mut input = encoded.clone();
+        input.push_str("\n# id:i+ tag:s +2\n");
+        let decoded: Vec<serde_json::Value> = decode(&input).unwrap();
+        assert_eq!(decoded.len(), 5002);
+        assert_eq!(decoded[5001], serde_json::json!({"id": 2, "tag": null}));
+    }
+
+    #[test]
+    fn test_sequence_and_delta_columns() {
+        // Steps vary, so `ts` is a delta column; rows also cross the
+        // rayon chunk size.
+        let rows: Vec<serde_json::Value> = (0..3000i64)
+            .map(|i| serde_json::json!({"ts": 1767225600 + i * 60 + i % 7, "slot": 900 + i * 15, "code": 3000 - i}))
+            .collect();
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.starts_with("# ts:i^ slot:i+900,15 code:i+3000,-1\n1767225600\n61\n61\n"));
+        let decoded: Vec<serde_json::Value> = decode(&encoded).unwrap();
+        assert_eq!(decoded, rows);
+
+        // Two values are always a sequence, so short columns stay plain.
+        let pair = serde_json::json!([{"n": 5}, {"n": 9}]);
+        assert_eq!(encode(&pair).unwrap(), "# n:i\n5\n9");
+
+        let overflow = format!("# n:i^\n{}\n1", i64::MAX);
+        assert!(matches!(decode::<serde_json::Value>(&overflow), Err(ZoonError::ParseError(_))));
+        assert!(matches!(decode::<serde_json::Value>("# n:i^\nx"), Err(ZoonError::ParseError(_))));
+    }
+
+    #[test]
+    fn test_temporal_columns() {
+        let rows = serde_json::json!([
+            {"day": "2025-12-28", "at": "2025-12-28T10:27:47Z", "took": "PT1H30M", "seen": "2025-12-28T10:27:47+01:00"},
+            {"day": "2026-01-02", "at": "2025-12-28T11:00:00Z", "took": "P1DT2H", "seen": "2025-12-28T10:27:47.5Z"},
+            {"day": null, "at": "2025-12-27T23:59:59Z", "took": "PT0S", "seen": null},
+        ]);
+        let encoded = encode(&rows).unwrap();
+        assert_eq!(
+            encoded,
+            "# day:d2025-12-28 at:dt2025-12-28T10:27:47Z took:du seen:dt\n\
+             0 0 5400 2025-12-28T10:27:47+01:00\n\
+             5 1933 93600 2025-12-28T10:27:47.5Z\n\
+             ~ -37668 0 ~"
+        );
+        assert_eq!(decode::<serde_json::Value>(&encoded).unwrap(), rows);
+
+        // Durations that would not come back as written stay strings.
+        let loose = serde_json::json!([{"took": "PT90M"}, {"took": "PT1H"}]);
+        assert_eq!(encode(&loose).unwrap(), "# took:s\nPT90M\nPT1H");
+
+        for bad in ["# day:d2025-13-01\n0", "# day:d2025-12-28\nx", "# day:d2025-12-28\n99999999"] {
+            assert!(matches!(decode::<serde_json::Value>(bad), Err(ZoonError::ParseError(_))), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_identifier_columns() {
+        let rows = serde_json::json!([
+            {"id": "0f8fad5b-d9cb-469f-a165-70867728950e", "user": "usr_8K2f", "commit": "9fceb02d0ae598e95dc970b74767f19372d61af8", "ref": "ord_7c9e6679-7425-40de-944b-e07fc1f90ae7"},
+            {"id": "7c9e6679-7425-40de-944b-e07fc1f90ae7", "user": "usr_Q1x", "commit": "0a4d55a8d778e5022fab701977c5d840bbc486d0", "ref": null},
+            {"id": null, "user": "usr_", "commit": "d670460b4b4aece5915caf5c68d12f560a9fe3e4", "ref": "ord_0f8fad5b-d9cb-469f-a165-70867728950e"},
+        ]);
+        let encoded = encode(&rows).unwrap();
+        assert_eq!(
+            encoded,
+            "# id:u user:s+usr_ commit:x ref:u+ord_\n\
+             D4-tW9nLRp-hZXCGdyiVDg 8K2f n86wLQrlmOldyXC3R2fxk3LWGvg fJ5meXQlQN6US-B_wfkK5w\n\
+             fJ5meXQlQN6US-B_wfkK5w Q1x Ck1VqNd45QIvq3AZd8XYQLvEhtA ~\n\
+             ~ \"\" 1nBGC0tK7OWRXK9caNEvVgqf4-Q D4-tW9nLRp-hZXCGdyiVDg"
+        );
+        assert_eq!(decode::<serde_json::Value>(&encoded).unwrap(), rows);
+
+        // Uppercase UUIDs would not come back as written.
+        let upper = serde_json::json!([{"id": "0F8FAD5B-D9CB-469F-A165-70867728950E"}]);
+        assert_eq!(encode(&upper).unwrap(), "# id:t\n\"0F8FAD5B-D9CB-469F-A165-70867728950E\"");
+
+        for bad in ["# id:u\nabc", "# id:u\nD4-tW9nLRp-hZXCGdyiVDh", "# id:x\n!!"] {
+            assert!(matches!(decode::<serde_json::Value>(bad), Err(ZoonError::ParseError(_))), "{}", bad);
+        }
+    }
+
+    #[cfg(feature = "uuid")]
+    #[test]
+    fn test_uuid_feature() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Session {
+            id: uuid::Uuid,
+            user: String,
+        }
+        let sessions: Vec<Session> = (1..=3u128)
+            .map(|n| Session { id: uuid::Uuid::from_u128(n << 64 | n), user: format!("usr_{}", n * 17) })
+            .collect();
+        let encoded = encode(&sessions).unwrap();
+        assert!(encoded.starts_with("# id:u user:s+usr_\nAAAAAAAAAAEAAAAAAAAAAQ 17\n"));
+        assert_eq!(decode::<Vec<Session>>(&encoded).unwrap(), sessions);
+    }
+
+    #[test]
+    fn test_default_columns() {
+        let rows: Vec<serde_json::Value> = (0..8)
+            .map(|i| {
+                let status = if i == 5 { "degraded" } else { "healthy" };
+                let zone = if i == 2 { serde_json::Value::Null } else { "eu-west-1".into() };
+                serde_json::json!({"status": status, "zone": zone, "port": if i == 4 { 9090 } else { 8080 }, "ms": i * 37 % 11})
+            })
+            .collect();
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.starts_with("# status:s@healthy zone:s@eu-west-1 port:i@8080 ms:i\n* * * 0\n* * * 4\n* ~ * 8\n"));
+        assert!(encoded.contains("\ndegraded * * 9\n"));
+        assert_eq!(decode::<Vec<serde_json::Value>>(&encoded).unwrap(), rows);
+
+        // A literal `*` cell would read as the default.
+        let mut stars = rows.clone();
+        stars[3]["status"] = "*".into();
+        assert!(!encode(&stars).unwrap().contains("status:s@"));
+
+        let decoded: serde_json::Value = decode("# note:s@all_good n:i@7\n* *\nfine ~").unwrap();
+        assert_eq!(decoded, serde_json::json!([{"note": "all good", "n": 7}, {"note": "fine", "n": null}]));
+
+        // `@` in enum options is not a default.
+        let owners = ["ann@example.com", "bob@example.com", "cy@example.com"];
+        let rows: Vec<serde_json::Value> = (0..9).map(|i| serde_json::json!({"owner": owners[i % 3]})).collect();
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.starts_with("# owner!ann@example.com|bob@example.com|cy@example.com\n0\n1\n2\n"));
+        assert_eq!(decode::<Vec<serde_json::Value>>(&encoded).unwrap(), rows);
+    }
+
+    #[test]
+    fn test_dictionary_columns() {
+        // Forty categories across two columns, far past the enum limit.
+        let (colours, animals) = (["red", "blue", "green", "amber", "violet"], ["fox", "owl", "elk", "yak", "emu", "ram", "cod", "bee"]);
+        let categories: Vec<String> = (0..40).map(|i| format!("{}-{}", colours[i % 5], animals[i / 5])).collect();
+        let rows: Vec<serde_json::Value> = (0..300)
+            .map(|i| {
+                let secondary = if i % 9 == 0 { serde_json::Value::Null } else { categories[(i * 13) % 40].clone().into() };
+                serde_json::json!({"sku": i * 31 % 997, "primary": categories[i % 40], "secondary": secondary})
+            })
+            .collect();
+        let encoded = encode(&rows).unwrap();
+        let mut lines = encoded.lines();
+        assert_eq!(lines.next().unwrap().split(' ').count(), 41);
+        assert_eq!(lines.next().unwrap(), "# sku:i primary:$ secondary:$");
+        assert_eq!(decode::<Vec<serde_json::Value>>(&encoded).unwrap(), rows);
+
+        // Few repeats do not pay for the dictionary line.
+        let unique: Vec<serde_json::Value> = (0..30).map(|i| serde_json::json!({"name": format!("user-{}", i)})).collect();
+        assert!(!encode(&unique).unwrap().contains('$'));
+
+        let err = decode::<serde_json::Value>("$ a b\n# x:$\n2").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(_)));
+
+        // Only a `$ ` line opens a table; `$a=x` is an inline entry.
+        for value in [serde_json::json!({"$a": "x"}), serde_json::json!({"$": {"b": 1}, "c": 2})] {
+            let decoded: serde_json::Value = decode(&encode(&value).unwrap()).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn test_reorder() {
+        // Readings in shuffled order; a note on only a few of them.
+        let rows: Vec<serde_json::Value> = (0..40)
+            .map(|i| {
+                let slot = i * 17 % 40;
+                let note = if slot % 13 == 0 { serde_json::Value::from("recalibrated") } else { serde_json::Value::Null };
+                serde_json::json!({"note": note, "ts": 1767225600 + slot * 60, "sensor": if slot % 2 == 0 { "a" } else { "b" }, "value": slot * 3 % 7})
+            })
+            .collect();
+        let encode_as = |reorder| encode_with_options(&rows, &EncodeOptions { reorder, ..Default::default() }).unwrap();
+        let plain = encode_as(Reorder::Off);
+        assert_eq!(plain, encode(&rows).unwrap());
+
+        let columns = encode_as(Reorder::Columns);
+        assert!(columns.starts_with("# ts:i sensor=a|b value:i note:s@recalibrated\n1767225600 a 0 *\n1767226620 b 2\n"));
+        assert!(columns.len() < plain.len());
+        assert_eq!(decode::<Vec<serde_json::Value>>(&columns).unwrap(), rows);
+
+        // Sorted by time, the timestamps become a sequence.
+        let sorted = encode_as(Reorder::Rows);
+        assert!(sorted.starts_with("# ts:i+1767225600,60 "));
+        let decoded: Vec<serde_json::Value> = decode(&sorted).unwrap();
+        assert_eq!(decoded.len(), rows.len());
+        assert!(decoded.windows(2).all(|w| w[0]["ts"].as_i64() < w[1]["ts"].as_i64()));
+
+        let kept = encode_as(Reorder::RowsKeepingOrder);
+        assert!(kept.starts_with("# :o ts:i+1767225600,60 "));
+        assert!(kept.len() < columns.len());
+        assert_eq!(decode::<Vec<serde_json::Value>>(&kept).unwrap(), rows);
+
+        for bad in ["# :o n:i\n0 5\n0 6", "# :o n:i\n0 5\n2 6", "# :o n:i\n0 5\n~ 6"] {
+            assert!(matches!(decode::<serde_json::Value>(bad).unwrap_err(), ZoonError::ParseError(_)));
+        }
+    }
+
+    #[test]
+    fn test_metadata() {
+        let metadata = Metadata {
+            entries: vec![("title".into(), "Team roster".into()), ("source".into(), "hr".into())],
+            comments: vec!["Exported nightly.\nDo not edit.".into()],
+        };
+        let options = EncodeOptions { metadata: metadata.clone(), ..Default::default() };
+        let rows = serde_json::json!([{"name": "Alice", "url": "//a"}, {"name": "Bob", "url": "//b"}]);
+        let encoded = encode_with_options(&rows, &options).unwrap();
+        assert_eq!(
+            encoded,
+            "//@title Team roster\n//@source hr\n// Exported nightly.\n// Do not edit.\n# name:s url:s\nAlice //a\nBob //b"
+        );
+        let read = read_metadata(&encoded);
+        assert_eq!(read.entries, metadata.entries);
+        assert_eq!(read.comments, vec!["Exported nightly.", "Do not edit."]);
+        assert_eq!(read.get("title"), Some("Team roster"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, rows);
+
+        // Comments may also sit between alias lines and ahead of inline documents.
+        let decoded: serde_json::Value = decode("%a=address\n// note\n# %a.city:s\nParis").unwrap();
+        assert_eq!(decoded, serde_json::json!([{"address": {"city": "Paris"}}]));
+        let config = serde_json::json!({"host": "localhost", "port": 3000});
+        let encoded = encode_with_options(&config, &options).unwrap();
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, config);
+
+        let bad = EncodeOptions { metadata: Metadata { entries: vec![("two words".into(), "x".into())], ..Default::default() }, ..Default::default() };
+        assert!(matches!(encode_with_options(&config, &bad), Err(ZoonError::InvalidFormat(_))));
+
+        // Only a top-level inline key starts a line, where it would read as
+        // a comment, an alias, a header or a section.
+        for key in ["//a", "%a", "#a", "## a"] {
+            let value = serde_json::json!({ key: 1, "b": "x" });
+            assert!(matches!(encode(&value), Err(ZoonError::UnsupportedType(_))), "{}", key);
+        }
+        for value in [
+            serde_json::json!({"b": {"//a": "x", "%a": 1, "#a": 2}}),
+            serde_json::json!([{"a": "//x", "b": 1}, {"a": "y", "b": 2}]),
+        ] {
+            let decoded: serde_json::Value = decode(&encode(&value).unwrap()).unwrap();
+            assert_eq!(decoded, value);
+            let pretty = EncodeOptions { pretty: Pretty::Nested, ..Default::default() };
+            let decoded: serde_json::Value = decode(&encode_with_options(&value, &pretty).unwrap()).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn test_sections() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Order {
+            id: i32,
+            total: f64,
+        }
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Prompt {
+            users: Vec<User>,
+            orders: Vec<Order>,
+            config: serde_json::Value,
+            archived: Vec<Order>,
+        }
+
+        let prompt = Prompt {
+            users: vec![
+                User { id: 1, name: "Alice".into(), role: "Admin".into(), active: true },
+                User { id: 2, name: "Bob".into(), role: "User".into(), active: false },
+            ],
+            orders: vec![Order { id: 7, total: 9.5 }, Order { id: 9, total: 12.0 }],
+            config: serde_json::json!({"region": "eu", "retries": 3}),
+            archived: Vec::new(),
+        };
+        let encoded = encode_sections(&prompt).unwrap();
+        assert!(encoded.starts_with("## users\n# id:i+ name:s role:s active:b\nAlice Admin 1\nBob User 0\n## orders\n"));
+        assert!(encoded.contains("\n## config\nregion=eu retries:3\n## archived"));
+
+        let decoded: Prompt = decode(&encoded).unwrap();
+        assert_eq!(decoded, prompt);
+        let map: serde_json::Map<String, serde_json::Value> = decode(&encoded).unwrap();
+        assert_eq!(map.keys().collect::<Vec<_>>(), vec!["users", "orders", "config", "archived"]);
+
+        let err = decode::<serde_json::Value>("## a\n# x:i\n1\n## a\nk:1").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(_)));
+        let options = DecodeOptions { max_rows: 3, ..Default::default() };
+        let err = decode_with_options::<serde_json::Value>("## a\n# x:i\n1\n2\n## b\n# y:i\n3\n4", &options).unwrap_err();
+        assert!(matches!(err, ZoonError::LimitExceeded(_)));
+        assert!(matches!(encode_sections(&vec![1, 2]), Err(ZoonError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_normalize() {
+        let acme = serde_json::json!({"id": 10, "name": "Acme Corp", "tier": "gold"});
+        let globex = serde_json::json!({"id": 20, "name": "Globex", "tier": "silver"});
+        let orders = serde_json::json!([
+            {"id": 1, "customer": acme, "total": 12.5},
+            {"id": 2, "customer": globex, "total": 3},
+            {"id": 3, "customer": acme, "total": 7},
+            {"id": 4, "customer": null, "total": 1},
+        ]);
+        let options = EncodeOptions { normalize: true, ..Default::default() };
+        let encoded = encode_with_options(&orders, &options).unwrap();
+        assert_eq!(
+            encoded,
+            "## customer\n# id:i name:s tier:s\n10 Acme_Corp gold\n20 Globex silver\n##\n# id:i+ customer>customer total:i\n0 12.5\n1 3\n0 7\n~ 1"
+        );
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, orders);
+
+        // Nothing repeats, so the plain table is written.
+        let flat = serde_json::json!([{"a": {"x": 1}}, {"a": {"x": 2}}]);
+        assert_eq!(encode_with_options(&flat, &options).unwrap(), encode(&flat).unwrap());
+
+        // `>` in a name is only a reference when nothing follows the table.
+        let arrows = serde_json::json!([
+            {"a>b": 1, "c>d": {"x": 1, "y": "p"}},
+            {"a>b": 2, "c>d": {"x": 2, "y": "q"}},
+            {"a>b": 5, "c>d": {"x": 1, "y": "p"}},
+        ]);
+        let encoded = encode_with_options(&arrows, &options).unwrap();
+        assert!(encoded.starts_with("# a>b:i c>d.x:i"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, arrows);
+    }
+
+    #[test]
+    fn test_array_cells() {
+        // Items that would read back as something else are written as JSON.
+        let rows = serde_json::json!([
+            {"id": 1, "tags": ["x", "y", "n"]},
+            {"id": 2, "tags": ["x,y", "1", "~", "", "a_b", "a b"]},
+            {"id": 3, "tags": [{"k": "v w"}, [1, [2]], true, null, 2.5]},
+            {"id": 4, "tags": ["]", "[", "\"quoted\""]},
+        ]);
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.contains(r#"[x,"y","n"]"#));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, rows);
+
+        // Lookup tables write their arrays the same way.
+        let ops = serde_json::json!({"id": 7, "members": ["y", "a_b"]});
+        let web = serde_json::json!({"id": 8, "members": ["1,2"]});
+        let tasks = serde_json::json!([{"id": 1, "team": ops}, {"id": 2, "team": web}, {"id": 3, "team": ops}]);
+        let options = EncodeOptions { normalize: true, ..Default::default() };
+        let encoded = encode_with_options(&tasks, &options).unwrap();
+        assert!(encoded.starts_with("## team"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, tasks);
+
+        // Inline arrays too.
+        let config = serde_json::json!({"hosts": ["a b", "c,d"], "flags": ["y", 1]});
+        let encoded = encode(&config).unwrap();
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, config);
+    }
+
+    #[test]
+    fn test_reference_errors() {
+        let err = decode::<serde_json::Value>("# id:i c>customer\n1 0").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(msg) if msg.contains("unknown table")));
+        let err = decode::<serde_json::Value>("## c\n# n:s\nx\n##\n# c>c\n5").unwrap_err();
+        assert!(matches!(err, ZoonError::ParseError(msg) if msg.contains("invalid reference")));
+
+        // Every reference copies its row, so copies count against the input limit.
+        let input = format!("## c\n# n:t\n\"{}\"\n##\n# c>c\n{}", "x".repeat(1000), "0\n".repeat(1000));
+        let options = DecodeOptions { max_total_bytes: 100_000, ..Default::default() };
+        let result = decode_with_options::<serde_json::Value>(&input, &options);
+        assert!(matches!(result, Err(ZoonError::LimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_hash_cells_are_quoted() {
+        let rows = serde_json::json!([{"tag": "#", "n": 1}, {"tag": "##", "n": 2}, {"tag": "#x", "n": 3}]);
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.contains("tag:t"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, rows);
+    }
+
+    #[test]
+    fn test_tokenize_row() {
+        let tokens: Vec<&str> = decode::tokenize_row(r#"  a "b \" c" [1,2]  ~ é"#).collect::<Result<_>>().unwrap();
+        assert_eq!(tokens, vec!["a", r#""b \" c""#, "[1,2]", "~", "é"]);
+    }
+
+    #[test]
+    fn test_string_columns_keep_types() {
+        // Found by the round-trip property tests.
+        let rows = serde_json::json!([
+            {"code": "007", "flag": "y", "note": "C:\\dir \"x\" and more text to make it long", "score": 0.5, "v": 1},
+            {"code": "42", "flag": "true", "note": "tab_separated_value, quoted \" twice \" here", "score": 1e20, "v": "a"},
+            {"code": "x", "flag": "n", "note": "a note with enough words to be quoted text", "score": -2.25, "v": "b"},
+            {"code": "y", "flag": "n", "note": "another note with enough words to be quoted", "score": 3.5, "v": "c"},
+        ]);
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.contains("note:t"));
+        assert!(encoded.contains("score:i"));
+        assert!(encoded.contains("100000000000000000000"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, rows);
+
+        // Columns mixing numbers and strings keep both.
+        let mixed = serde_json::json!([{"a": 1}, {"a": "x"}, {"a": "y"}]);
+        let encoded = encode(&mixed).unwrap();
+        assert!(encoded.contains("a:t"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, mixed);
+
+        let mixed = serde_json::json!([{"v": 1}, {"v": "a"}, {"v": "b"}, {"v": "a"}, {"v": "b"}, {"v": "a"}, {"v": true}, {"v": "1"}]);
+        let decoded: serde_json::Value = decode(&encode(&mixed).unwrap()).unwrap();
+        assert_eq!(decoded, mixed);
+
+        // Arrays among other kinds come back as arrays.
+        let mixed = serde_json::json!([{"a": 1}, {"a": [1, 2]}, {"a": []}, {"a": "[x]"}]);
+        let encoded = encode(&mixed).unwrap();
+        assert!(encoded.contains("a:t"));
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, mixed);
+
+        let tag = serde_json::json!({"id": 1, "tags": []});
+        let other = serde_json::json!({"id": 2, "tags": "x"});
+        let rows = serde_json::json!([{"t": tag}, {"t": other}, {"t": tag}, {"t": other}]);
+        let options = EncodeOptions { normalize: true, ..Default::default() };
+        let decoded: serde_json::Value = decode(&encode_with_options(&rows, &options).unwrap()).unwrap();
+        assert_eq!(decoded, rows);
+
+        // `|` separates enum options, so its values stay cells.
+        let rows = serde_json::json!([{"v": "|"}, {"v": "a|b"}, {"v": "|"}, {"v": "a|b"}]);
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.starts_with("# v:s\n"));
+        assert_eq!(decode::<serde_json::Value>(&encoded).unwrap(), rows);
+
+        // Line breaks are escaped so a cell stays on its row, and other
+        // whitespace is quoted, also out of hoisted prefixes.
+        let rows = serde_json::json!([{"v": "a\nb", "w": "x\u{2029}y1"}, {"v": "c\r\n", "w": "x\u{2029}y2"}, {"v": "\\n", "w": "x\ty"}]);
+        let encoded = encode(&rows).unwrap();
+        assert!(encoded.contains("\"a\\nb\""));
+        assert_eq!(encoded.lines().count(), 4);
+        assert_eq!(decode::<serde_json::Value>(&encoded).unwrap(), rows);
+    }
+
+    /// Every whitespace-separated word is one token; underscores cost extra.
+    struct WordCost;
+
+    impl TokenCost for WordCost {
+        fn cost(&self, text: &str) -> usize {
+            text.split_whitespace().count() + text.matches('_').count() * 3
+        }
+    }
+
+    #[test]
+    fn test_encode_with_cost_model() {
+        use std::sync::Arc;
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Order {
+            no: i32,
+            status: String,
+            note: String,
+        }
+        let statuses = ["processing", "delivered", "cancelled"];
+        let data: Vec<Order> = (0..6)
+            .map(|i| Order { no: i * 7, status: statuses[i as usize % 3].into(), note: format!("left at door {}", i) })
+            .collect();
+        let options = EncodeOptions { cost_model: Some(Arc::new(WordCost)), ..Default::default() };
+
+        // Indices cost as much as words here, so literal enums win, and
+        // quoting beats underscores.
+        let encoded = encode_with_options(&data, &options).unwrap();
+        assert!(encoded.contains("note:t"));
+        assert!(encoded.contains("status=cancelled|delivered|processing"));
+        assert!(encoded.contains("\ndelivered \"left at door 1\"\n"));
+        let decoded: Vec<Order> = decode(&encoded).unwrap();
+        assert_eq!(decoded, data);
+
+        let default = encode(&data).unwrap();
+        assert!(default.contains("note:s"));
+        assert!(default.contains("status!cancelled|delivered|processing"));
+
+        // Hoisting `usr_` spares quoting every id, unless the model prices
+        // the hoisted header above the quotes.
+        struct PlusCost;
+        impl TokenCost for PlusCost {
+            fn cost(&self, text: &str) -> usize {
+                text.len() + text.matches('+').count() * 50
+            }
+        }
+        let users = serde_json::json!([{"id": "usr_a1"}, {"id": "usr_b2"}, {"id": "usr_c3"}]);
+        let encoded = encode_with_options(&users, &options).unwrap();
+        assert_eq!(encoded, "# id:s+usr_\na1\nb2\nc3");
+        let options = EncodeOptions { cost_model: Some(Arc::new(PlusCost)), ..Default::default() };
+        let encoded = encode_with_options(&users, &options).unwrap();
+        assert_eq!(encoded, "# id:t\n\"usr_a1\"\n\"usr_b2\"\n\"usr_c3\"");
+        let decoded: serde_json::Value = decode(&encoded).unwrap();
+        assert_eq!(decoded, users);
+    }
+
+    #[test]
+    fn test_aliases_with_cost_model() {
+        use std::sync::Arc;
+
+        #[derive(Serialize)]
+        struct Status {
+            state: String,
+        }
+        #[derive(Serialize)]
+        struct Infra {
+            postgres: Status,
+            redis: Status,
+        }
+        #[derive(Serialize)]
+        struct System {
+            infrastructure: Infra,
+        }
+        let data: Vec<System> = ["up", "down"]
+            .iter()
+            .map(|s| System { infrastructure: Infra { postgres: Status { state: s.to_string() }, redis: Status { state: s.to_string() } } })
+            .collect();
+
+        // Dotted names are single words, so an alias only adds its definition.
+        let options = EncodeOptions { cost_model: Some(Arc::new(WordCost)), ..Default::default() };
+        let encoded = encode_with_options(&data, &options).unwrap();
+        assert!(!encoded.contains('%'));
+        assert!(encoded.contains("infrastructure.postgres.state"));
+    }
 }
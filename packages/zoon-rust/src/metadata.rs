@@ -0,0 +1,78 @@
+//! Comment and metadata lines ahead of a document (SPEC §4.6).
+//!
+//! Before the header (or an inline document), `// text` lines are comments
+//! and `//@key value` lines are metadata entries such as a table title.
+//! Decoders skip both; [`read_metadata`] returns them.
+
+use crate::{Result, ZoonError};
+
+/// Comments and metadata entries of a document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// `//@key value` entries in document order.
+    pub entries: Vec<(String, String)>,
+    /// Comment lines without the leading `// `.
+    pub comments: Vec<String>,
+}
+
+impl Metadata {
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.comments.is_empty()
+    }
+
+    /// Value of the first entry named `key`.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+
+    /// Entries followed by comments, one line each, without a trailing
+    /// newline. Multi-line comments become several comment lines.
+    pub(crate) fn to_lines(&self) -> Result<String> {
+        let mut lines = Vec::new();
+        for (key, value) in &self.entries {
+            if key.is_empty() || key.contains(char::is_whitespace) {
+                return Err(ZoonError::InvalidFormat(format!("invalid metadata key {:?}", key)));
+            }
+            if value.contains(['\n', '\r']) {
+                return Err(ZoonError::InvalidFormat(format!("metadata value for {} spans lines", key)));
+            }
+            lines.push(if value.is_empty() { format!("//@{}", key) } else { format!("//@{} {}", key, value) });
+        }
+        for comment in &self.comments {
+            for line in comment.lines() {
+                lines.push(if line.is_empty() { "//".to_string() } else { format!("// {}", line) });
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+}
+
+/// Reads the comments and metadata before the header or inline body.
+/// Alias and dictionary lines in between are skipped.
+pub fn read_metadata(input: &str) -> Metadata {
+    let mut metadata = Metadata::default();
+    for line in input.lines().map(str::trim) {
+        if let Some(entry) = line.strip_prefix("//@") {
+            let (key, value) = entry.split_once(' ').unwrap_or((entry, ""));
+            metadata.entries.push((key.to_string(), value.trim().to_string()));
+        } else if let Some(comment) = line.strip_prefix("//") {
+            metadata.comments.push(comment.strip_prefix(' ').unwrap_or(comment).to_string());
+        } else if !line.is_empty() && !line.starts_with(['%', '$']) {
+            break;
+        }
+    }
+    metadata
+}
+
+pub(crate) fn is_comment_line(line: &str) -> bool {
+    line.starts_with("//")
+}
+
+/// `input` without its leading blank and comment lines.
+pub(crate) fn skip_comments(input: &str) -> &str {
+    let mut rest = input.trim_start();
+    while is_comment_line(rest) {
+        rest = rest.split_once('\n').map_or("", |(_, tail)| tail).trim_start();
+    }
+    rest
+}
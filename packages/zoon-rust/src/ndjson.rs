@@ -0,0 +1,305 @@
+//! Streaming conversion between JSON Lines (NDJSON) and tabular ZOON.
+//!
+//! Each JSON line becomes one ZOON row. The header is inferred from the first
+//! `sample_size` lines, so rows can be written as soon as the sample window is
+//! full instead of after the whole input has been read.
+
+use crate::decode::{DecodeOptions, RowDecoder};
+use crate::encode::{flatten_object, format_cell, needs_quotes};
+use crate::{Result, ZoonError};
+use std::io::{BufRead, Write};
+
+/// What to do when a line after the sample window does not fit the header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SchemaFallback {
+    /// Emit a new, widened header line and keep streaming.
+    #[default]
+    Reheader,
+    /// Drop keys the header does not know about.
+    Ignore,
+    /// Fail with `ZoonError::InvalidFormat`.
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct NdjsonOptions {
+    /// Number of lines read before the header is written.
+    pub sample_size: usize,
+    pub fallback: SchemaFallback,
+}
+
+impl Default for NdjsonOptions {
+    fn default() -> Self {
+        NdjsonOptions {
+            sample_size: 100,
+            fallback: SchemaFallback::Reheader,
+        }
+    }
+}
+
+/// Converts JSON Lines from `reader` into tabular ZOON written to `writer`.
+///
+/// Streamed headers only use `i`, `b`, `a`, `s` and `t` columns: constants,
+/// auto-increment ids and enums cannot be decided from a sample.
+pub fn ndjson_to_zoon<R: BufRead, W: Write>(reader: R, mut writer: W, options: &NdjsonOptions) -> Result<()> {
+    let mut sample: Vec<serde_json::Map<String, serde_json::Value>> = Vec::new();
+    let mut schema: Option<Schema> = None;
+
+    for (idx, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| ZoonError::Io(e.to_string()))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let row = parse_line(line, idx + 1)?;
+
+        match schema.as_mut() {
+            None => {
+                sample.push(row);
+                if sample.len() >= options.sample_size.max(1) {
+                    schema = Some(write_sample(&mut writer, &sample)?);
+                    sample.clear();
+                }
+            }
+            Some(schema) => {
+                if !schema.accepts(&row) {
+                    match options.fallback {
+                        SchemaFallback::Reheader => {
+                            schema.widen(&row);
+                            write_line(&mut writer, &schema.header())?;
+                        }
+                        SchemaFallback::Ignore => {}
+                        SchemaFallback::Error => {
+                            return Err(ZoonError::InvalidFormat(format!(
+                                "line {} does not match the inferred schema",
+                                idx + 1
+                            )));
+                        }
+                    }
+                }
+                write_line(&mut writer, &schema.row(&row))?;
+            }
+        }
+    }
+
+    if schema.is_none() && !sample.is_empty() {
+        write_sample(&mut writer, &sample)?;
+    }
+
+    writer.flush().map_err(|e| ZoonError::Io(e.to_string()))
+}
+
+/// Converts tabular ZOON from `reader` into JSON Lines written to `writer`.
+pub fn zoon_to_ndjson<R: BufRead, W: Write>(reader: R, mut writer: W) -> Result<()> {
+    let mut decoder = RowDecoder::new(DecodeOptions::streaming());
+    let mut rows = Vec::new();
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| ZoonError::Io(e.to_string()))?;
+        decoder.push_line(&line, &mut rows)?;
+        for row in rows.drain(..) {
+            let json = serde_json::to_string(&row).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+            write_line(&mut writer, &json)?;
+        }
+    }
+
+    writer.flush().map_err(|e| ZoonError::Io(e.to_string()))
+}
+
+fn parse_line(line: &str, line_no: usize) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let value: serde_json::Value = serde_json::from_str(line)
+        .map_err(|e| ZoonError::ParseError(format!("line {}: {}", line_no, e)))?;
+    if !value.is_object() {
+        return Err(ZoonError::InvalidFormat(format!("line {}: expected a JSON object", line_no)));
+    }
+    let mut flat = serde_json::Map::new();
+    flatten_object("", &value, &mut flat);
+    Ok(flat)
+}
+
+fn write_sample<W: Write>(writer: &mut W, sample: &[serde_json::Map<String, serde_json::Value>]) -> Result<Schema> {
+    let mut schema = Schema::default();
+    for row in sample {
+        schema.widen(row);
+    }
+    write_line(writer, &schema.header())?;
+    for row in sample {
+        write_line(writer, &schema.row(row))?;
+    }
+    Ok(schema)
+}
+
+fn write_line<W: Write>(writer: &mut W, line: &str) -> Result<()> {
+    writeln!(writer, "{}", line).map_err(|e| ZoonError::Io(e.to_string()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    /// Only nulls seen so far.
+    Unknown,
+    Int,
+    Bool,
+    Array,
+    Str,
+    /// Long strings, strings that must be quoted, and mixed kinds.
+    Text,
+}
+
+impl Kind {
+    fn of(value: &serde_json::Value) -> Kind {
+        match value {
+            serde_json::Value::Null => Kind::Unknown,
+            serde_json::Value::Bool(_) => Kind::Bool,
+            serde_json::Value::Number(_) => Kind::Int,
+            serde_json::Value::Array(_) => Kind::Array,
+            serde_json::Value::String(s) if s.len() > 30 || needs_quotes(s) => Kind::Text,
+            _ => Kind::Str,
+        }
+    }
+
+    fn join(self, other: Kind) -> Kind {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Kind::Unknown, k) | (k, Kind::Unknown) => k,
+            // Quoted `t` cells stay strings and the rest read by their token.
+            _ => Kind::Text,
+        }
+    }
+
+    fn type_code(self) -> &'static str {
+        match self {
+            Kind::Int => "i",
+            Kind::Bool => "b",
+            Kind::Array => "a",
+            Kind::Text => "t",
+            Kind::Unknown | Kind::Str => "s",
+        }
+    }
+}
+
+struct Column {
+    name: String,
+    kind: Kind,
+}
+
+/// Header columns inferred from the rows seen so far.
+#[derive(Default)]
+pub(crate) struct Schema {
+    columns: Vec<Column>,
+}
+
+impl Schema {
+    pub(crate) fn accepts(&self, row: &serde_json::Map<String, serde_json::Value>) -> bool {
+        row.iter().all(|(key, val)| match self.columns.iter().find(|c| &c.name == key) {
+            Some(col) => col.kind.join(Kind::of(val)) == col.kind,
+            None => false,
+        })
+    }
+
+    pub(crate) fn widen(&mut self, row: &serde_json::Map<String, serde_json::Value>) {
+        for (key, val) in row {
+            match self.columns.iter_mut().find(|c| &c.name == key) {
+                Some(col) => col.kind = col.kind.join(Kind::of(val)),
+                None => self.columns.push(Column {
+                    name: key.clone(),
+                    kind: Kind::of(val),
+                }),
+            }
+        }
+    }
+
+    pub(crate) fn header(&self) -> String {
+        let mut parts = vec!["#".to_string()];
+        for col in &self.columns {
+            parts.push(format!("{}:{}", col.name.replace(' ', "_"), col.kind.type_code()));
+        }
+        parts.join(" ")
+    }
+
+    pub(crate) fn row(&self, row: &serde_json::Map<String, serde_json::Value>) -> String {
+        let cells: Vec<String> = self
+            .columns
+            .iter()
+            .map(|col| {
+                let val = row.get(&col.name).unwrap_or(&serde_json::Value::Null);
+                format_cell(val, col.kind.type_code())
+            })
+            .collect();
+        cells.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_zoon(input: &str, options: &NdjsonOptions) -> Result<String> {
+        let mut out = Vec::new();
+        ndjson_to_zoon(input.as_bytes(), &mut out, options)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_ndjson_to_zoon() {
+        let input = "{\"id\":1,\"name\":\"Alice Smith\",\"admin\":true}\n{\"id\":2,\"name\":\"Bob\",\"admin\":false}\n";
+        let zoon = to_zoon(input, &NdjsonOptions::default()).unwrap();
+        assert_eq!(zoon, "# id:i name:s admin:b\n1 Alice_Smith 1\n2 Bob 0\n");
+    }
+
+    #[test]
+    fn test_ndjson_roundtrip() {
+        let input = "{\"user\":{\"name\":\"Alice\"},\"score\":1.5,\"tags\":[\"a\",\"b\"]}\n{\"user\":{\"name\":\"Bob\"},\"score\":2,\"tags\":[]}\n";
+        let zoon = to_zoon(input, &NdjsonOptions::default()).unwrap();
+
+        let mut out = Vec::new();
+        zoon_to_ndjson(zoon.as_bytes(), &mut out).unwrap();
+        let lines: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        let expected: Vec<serde_json::Value> = input.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(lines, expected);
+    }
+
+    #[test]
+    fn test_ndjson_quotes_and_mixed_kinds() {
+        let input = "{\"s\":\"a_b\",\"v\":1}\n{\"s\":\"~\",\"v\":true}\n{\"s\":\"[x\",\"v\":\"x\"}\n{\"s\":\"*\",\"v\":[1]}\n";
+        let zoon = to_zoon(input, &NdjsonOptions::default()).unwrap();
+        assert!(zoon.starts_with("# s:t v:t\n\"a_b\" 1\n"));
+
+        let decoded: Vec<serde_json::Value> = crate::decode(&zoon).unwrap();
+        let expected: Vec<serde_json::Value> = input.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(decoded, expected);
+
+        // A row after the sample that no longer fits widens the column to `t`.
+        let input = "{\"v\":1}\n{\"v\":\"a b\"}\n";
+        let zoon = to_zoon(input, &NdjsonOptions { sample_size: 1, ..Default::default() }).unwrap();
+        assert_eq!(zoon, "# v:i\n1\n# v:t\n\"a b\"\n");
+        let decoded: Vec<serde_json::Value> = crate::decode(&zoon).unwrap();
+        assert_eq!(decoded, vec![serde_json::json!({"v": 1}), serde_json::json!({"v": "a b"})]);
+    }
+
+    #[test]
+    fn test_ndjson_new_keys_after_sample() {
+        let input = "{\"a\":1}\n{\"a\":2}\n{\"a\":3,\"b\":\"x\"}\n";
+        let options = NdjsonOptions { sample_size: 2, ..Default::default() };
+        let zoon = to_zoon(input, &options).unwrap();
+        assert_eq!(zoon, "# a:i\n1\n2\n# a:i b:s\n3 x\n");
+
+        let decoded: Vec<serde_json::Value> = crate::decode(&zoon).unwrap();
+        assert_eq!(decoded[2]["b"], "x");
+
+        let ignored = to_zoon(input, &NdjsonOptions { fallback: SchemaFallback::Ignore, ..options.clone() }).unwrap();
+        assert_eq!(ignored, "# a:i\n1\n2\n3\n");
+
+        let err = to_zoon(input, &NdjsonOptions { fallback: SchemaFallback::Error, ..options }).unwrap_err();
+        assert!(matches!(err, ZoonError::InvalidFormat(_)));
+    }
+
+    #[test]
+    fn test_ndjson_rejects_non_objects() {
+        let err = to_zoon("[1,2]\n", &NdjsonOptions::default()).unwrap_err();
+        assert!(matches!(err, ZoonError::InvalidFormat(_)));
+    }
+}
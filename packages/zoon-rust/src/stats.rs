@@ -0,0 +1,165 @@
+//! Token statistics for JSON vs ZOON output, counted with a real BPE
+//! vocabulary instead of a characters-per-token estimate.
+
+use crate::bpe::Vocabulary;
+use crate::decode::{is_header_line, is_tabular, read_header, tokenize_row, DecodeOptions};
+use crate::metadata::skip_comments;
+use crate::{Result, ZoonError};
+use serde::Serialize;
+
+/// Token counts for one value encoded as compact JSON and as ZOON.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenStats {
+    pub json_tokens: usize,
+    pub zoon_tokens: usize,
+    /// Per-column cost for tabular output, in header order. Empty for inline
+    /// documents.
+    pub columns: Vec<ColumnCost>,
+}
+
+/// Tokens spent on one tabular column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnCost {
+    /// Field name with aliases expanded.
+    pub name: String,
+    /// Tokens of the header entry (` name:type`, ` @name=value`).
+    pub header_tokens: usize,
+    /// Tokens of the column's cells across all rows, separators included.
+    pub body_tokens: usize,
+}
+
+impl ColumnCost {
+    pub fn total(&self) -> usize {
+        self.header_tokens + self.body_tokens
+    }
+}
+
+impl TokenStats {
+    /// Tokens saved by ZOON, negative when ZOON is larger.
+    pub fn saved(&self) -> isize {
+        self.json_tokens as isize - self.zoon_tokens as isize
+    }
+
+    /// Saved tokens as a percentage of the JSON cost.
+    pub fn savings_percent(&self) -> f64 {
+        if self.json_tokens == 0 {
+            return 0.0;
+        }
+        self.saved() as f64 * 100.0 / self.json_tokens as f64
+    }
+}
+
+/// Encodes `value` as compact JSON and as ZOON and counts both with `vocab`.
+pub fn token_stats<T: Serialize>(value: &T, vocab: &Vocabulary) -> Result<TokenStats> {
+    let json = serde_json::to_string(value).map_err(|e| ZoonError::UnsupportedType(e.to_string()))?;
+    let zoon = crate::encode(value)?;
+    let mut stats = zoon_stats(&zoon, vocab)?;
+    stats.json_tokens = vocab.count(&json);
+    Ok(stats)
+}
+
+/// Counts tokens of an existing ZOON document; `json_tokens` is left at 0.
+pub fn zoon_stats(zoon: &str, vocab: &Vocabulary) -> Result<TokenStats> {
+    let columns = if is_tabular(skip_comments(zoon)) {
+        column_costs(zoon, vocab)?
+    } else {
+        Vec::new()
+    };
+    Ok(TokenStats {
+        json_tokens: 0,
+        zoon_tokens: vocab.count(zoon),
+        columns,
+    })
+}
+
+fn column_costs(zoon: &str, vocab: &Vocabulary) -> Result<Vec<ColumnCost>> {
+    let (header, body) = read_header(zoon, &DecodeOptions::default())?;
+    let header_line = zoon
+        .lines()
+        .map(str::trim)
+        .find(|l| l.starts_with('#'))
+        .unwrap_or_default();
+
+    // Header parts line up with constants and fields in the order they appear.
+    let mut constants = header.constants.iter();
+    let mut fields = header.fields.iter();
+    let mut columns = Vec::new();
+    let mut body_columns = Vec::new();
+    for part in header_line.trim_start_matches('#').split_whitespace() {
+        let header_tokens = vocab.count(&format!(" {}", part));
+        if part.starts_with('@') {
+            if let Some(c) = constants.next() {
+                columns.push(ColumnCost { name: c.name.clone(), header_tokens, body_tokens: 0 });
+            }
+        } else if !part.starts_with('+') && part.contains([':', '=', '!', '>']) {
+            if let Some(f) = fields.next() {
+                if f.typ != "i+" {
+                    body_columns.push(columns.len());
+                }
+                columns.push(ColumnCost { name: f.name.clone(), header_tokens, body_tokens: 0 });
+            }
+        }
+    }
+
+    for line in body {
+        let line = line.trim();
+        if line.is_empty() || is_header_line(line) {
+            continue;
+        }
+        for (i, cell) in tokenize_row(line).enumerate() {
+            let Some(&col) = body_columns.get(i) else { break };
+            let cell = cell?;
+            let text = if i == 0 { cell.to_string() } else { format!(" {}", cell) };
+            // Newline separators are charged to the first column.
+            columns[col].body_tokens += vocab.count(&text) + usize::from(i == 0);
+        }
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bpe::tests::test_vocabulary;
+
+    #[test]
+    fn test_token_stats() {
+        #[derive(Serialize)]
+        struct Row {
+            id: i32,
+            name: String,
+            team: String,
+        }
+        let rows: Vec<Row> = (1..=3)
+            .map(|i| Row { id: i, name: format!("user{}", i), team: "core".into() })
+            .collect();
+        let vocab = test_vocabulary(&["us", "er", "user", " user", "na", "me", "name", " name", "co", "re", "core", "id"]);
+
+        let stats = token_stats(&rows, &vocab).unwrap();
+        assert!(stats.json_tokens > stats.zoon_tokens);
+        assert!(stats.savings_percent() > 50.0);
+
+        let names: Vec<&str> = stats.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["team", "id", "name"]);
+        assert_eq!(stats.columns[0].body_tokens, 0);
+        assert_eq!(stats.columns[1].body_tokens, 0);
+        // "user1", "user2", "user3" plus a newline each.
+        assert_eq!(stats.columns[2].body_tokens, 9);
+    }
+
+    #[test]
+    fn test_inline_stats() {
+        let vocab = test_vocabulary(&[]);
+        let stats = zoon_stats("host=localhost port:3000", &vocab).unwrap();
+        assert!(stats.columns.is_empty());
+        assert_eq!(stats.zoon_tokens, "host=localhost port:3000".len());
+
+        // Alias lines and `$` entries start inline documents too.
+        for zoon in ["%s=services %a=%s.api\n%a.host=api.internal %a.port:80", "$a=x", "// note\n%a.b:1"] {
+            let stats = zoon_stats(zoon, &vocab).unwrap();
+            assert!(stats.columns.is_empty(), "{}", zoon);
+        }
+        let stats = zoon_stats("%a=user\n# %a.id:i\n1\n2", &vocab).unwrap();
+        assert_eq!(stats.columns.len(), 1);
+    }
+}
@@ -0,0 +1,204 @@
+//! Dates, datetimes and durations for `d`, `dt` and `du` columns
+//! (SPEC §4.10).
+//!
+//! Values are ISO 8601 text: `2025-12-28`, `2025-12-28T10:27:47Z` and
+//! `PT1H30M`. Datetimes keep their wall-clock time and zone suffix as
+//! written, so offsets never shift them between zones.
+
+use crate::{Result, ZoonError};
+
+/// A parsed `YYYY-MM-DDTHH:MM:SS[.fff][Z|±HH:MM]`.
+pub(crate) struct DateTime<'a> {
+    /// Wall-clock seconds since `1970-01-01T00:00:00`.
+    pub(crate) seconds: i64,
+    /// Fractional seconds, dot included, or empty.
+    pub(crate) fraction: &'a str,
+    /// `Z`, `±HH:MM` or empty.
+    pub(crate) zone: &'a str,
+}
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Fixed-width decimal field.
+fn digits(s: &str) -> Option<i64> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD` date.
+pub(crate) fn parse_date(s: &str) -> Option<i64> {
+    let b = s.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let (year, month, day) = (digits(&s[..4])?, digits(&s[5..7])?, digits(&s[8..])?);
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+pub(crate) fn format_date(days: i64) -> Result<String> {
+    let (year, month, day) = civil_from_days(days);
+    if !(0..=9999).contains(&year) {
+        return Err(ZoonError::ParseError(format!("date out of range: {} days", days)));
+    }
+    Ok(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+pub(crate) fn parse_datetime(s: &str) -> Option<DateTime<'_>> {
+    let b = s.as_bytes();
+    if b.len() < 19 || !b[..19].is_ascii() || b[10] != b'T' || b[13] != b':' || b[16] != b':' {
+        return None;
+    }
+    let days = parse_date(&s[..10])?;
+    let (hour, minute, second) = (digits(&s[11..13])?, digits(&s[14..16])?, digits(&s[17..19])?);
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let rest = &s[19..];
+    let fraction_len = match rest.strip_prefix('.') {
+        Some(frac) => 1 + frac.bytes().take_while(u8::is_ascii_digit).count(),
+        None => 0,
+    };
+    let (fraction, zone) = rest.split_at(fraction_len);
+    if fraction.len() == 1 || fraction.len() > 10 {
+        return None;
+    }
+    zone_offset(zone)?;
+    Some(DateTime { seconds: days * 86400 + hour * 3600 + minute * 60 + second, fraction, zone })
+}
+
+/// `seconds` as `YYYY-MM-DDTHH:MM:SS` followed by `zone`.
+pub(crate) fn format_datetime(seconds: i64, zone: &str) -> Result<String> {
+    let date = format_date(seconds.div_euclid(86400))?;
+    let time = seconds.rem_euclid(86400);
+    Ok(format!("{}T{:02}:{:02}:{:02}{}", date, time / 3600, time / 60 % 60, time % 60, zone))
+}
+
+/// Offset in seconds of a zone suffix; an empty zone counts as UTC.
+pub(crate) fn zone_offset(zone: &str) -> Option<i32> {
+    match zone {
+        "" | "Z" => Some(0),
+        _ => {
+            let b = zone.as_bytes();
+            let sign = match b.first() {
+                Some(b'+') => 1,
+                Some(b'-') => -1,
+                _ => return None,
+            };
+            if b.len() != 6 || b[3] != b':' {
+                return None;
+            }
+            let (hours, minutes) = (digits(&zone[1..3])?, digits(&zone[4..])?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            Some(sign * (hours * 3600 + minutes * 60) as i32)
+        }
+    }
+}
+
+/// Seconds of an ISO 8601 duration such as `P1DT2H`, `PT90M` or `-PT5S`.
+pub(crate) fn parse_duration(s: &str) -> Option<i64> {
+    let (sign, rest) = match s.strip_prefix('-') {
+        Some(rest) => (-1, rest),
+        None => (1, s),
+    };
+    let rest = rest.strip_prefix('P')?;
+    let (date, time) = match rest.split_once('T') {
+        Some((date, time)) if !time.is_empty() => (date, Some(time)),
+        Some(_) => return None,
+        None => (rest, None),
+    };
+    if date.is_empty() && time.is_none() {
+        return None;
+    }
+    let mut total: i64 = 0;
+    if !date.is_empty() {
+        total = digits(date.strip_suffix('D')?)?.checked_mul(86400)?;
+    }
+    let mut units = [('H', 3600), ('M', 60), ('S', 1)].iter();
+    let mut time = time.unwrap_or("");
+    while !time.is_empty() {
+        let end = time.find(|c: char| !c.is_ascii_digit())?;
+        let unit = time[end..].chars().next()?;
+        let (_, scale) = units.by_ref().find(|(u, _)| *u == unit)?;
+        total = total.checked_add(digits(&time[..end])?.checked_mul(*scale)?)?;
+        time = &time[end + 1..];
+    }
+    total.checked_mul(sign)
+}
+
+/// Canonical ISO 8601 form of a duration: days, hours, minutes and
+/// seconds, zero parts left out, `PT0S` for zero.
+pub(crate) fn format_duration(seconds: i64) -> String {
+    if seconds == 0 {
+        return "PT0S".into();
+    }
+    let sign = if seconds < 0 { "-" } else { "" };
+    let total = seconds.unsigned_abs();
+    let (days, hours, minutes, secs) = (total / 86400, total / 3600 % 24, total / 60 % 60, total % 60);
+    let mut out = format!("{}P", sign);
+    if days > 0 {
+        out.push_str(&format!("{}D", days));
+    }
+    if hours + minutes + secs > 0 {
+        out.push('T');
+        for (n, unit) in [(hours, 'H'), (minutes, 'M'), (secs, 'S')] {
+            if n > 0 {
+                out.push_str(&format!("{}{}", n, unit));
+            }
+        }
+    }
+    out
+}
+
+/// Base of a `d` (days) or `dt` (seconds and zone) column.
+pub(crate) type Base = (i64, String);
+
+/// Splits a column type with a base, `d2025-12-01` or
+/// `dt2025-12-28T10:00:00Z`, into the bare type and the base.
+pub(crate) fn split_base(typ: &str, name: &str) -> Result<(String, Option<Base>)> {
+    let invalid = || ZoonError::ParseError(format!("invalid base {} for {}", typ, name));
+    if let Some(base) = typ.strip_prefix("dt").filter(|b| !b.is_empty()) {
+        let parsed = parse_datetime(base).filter(|dt| dt.fraction.is_empty()).ok_or_else(invalid)?;
+        return Ok(("dt".into(), Some((parsed.seconds, parsed.zone.to_string()))));
+    }
+    if let Some(base) = typ.strip_prefix('d').filter(|b| b.starts_with(|c: char| c.is_ascii_digit())) {
+        let days = parse_date(base).ok_or_else(invalid)?;
+        return Ok(("d".into(), Some((days, String::new()))));
+    }
+    Ok((typ.to_string(), None))
+}
@@ -0,0 +1,154 @@
+//! Serde helpers for `time` types (feature `time`).
+//!
+//! `time` has no ISO 8601 serde form without its own format features, so
+//! these modules write the text the encoder turns into `d`, `dt` and `du`
+//! columns:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Job {
+//!     #[serde(with = "zoon::time::datetime")]
+//!     started: time::OffsetDateTime,
+//!     #[serde(with = "zoon::time::duration")]
+//!     took: time::Duration,
+//! }
+//! ```
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+
+fn text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    String::deserialize(deserializer)
+}
+
+/// `Date` as `YYYY-MM-DD`.
+pub mod date {
+    use super::*;
+    use crate::temporal::{format_date, parse_date};
+    use ::time::Date;
+    use serde::ser::Error as _;
+    use serde::Serializer;
+
+    /// Julian day of 1970-01-01.
+    const UNIX_EPOCH_JULIAN_DAY: i64 = 2440588;
+
+    pub fn serialize<S: Serializer>(date: &Date, serializer: S) -> Result<S::Ok, S::Error> {
+        let days = date.to_julian_day() as i64 - UNIX_EPOCH_JULIAN_DAY;
+        serializer.serialize_str(&format_date(days).map_err(S::Error::custom)?)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Date, D::Error> {
+        let text = text(deserializer)?;
+        let days = parse_date(&text).ok_or_else(|| D::Error::custom(format!("invalid date {}", text)))?;
+        Date::from_julian_day((days + UNIX_EPOCH_JULIAN_DAY) as i32).map_err(D::Error::custom)
+    }
+}
+
+/// `OffsetDateTime` as `YYYY-MM-DDTHH:MM:SS[.fff]` followed by `Z` or
+/// `±HH:MM`.
+pub mod datetime {
+    use super::*;
+    use crate::temporal::{format_datetime, parse_datetime, zone_offset};
+    use ::time::{OffsetDateTime, UtcOffset};
+    use serde::ser::Error as _;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(datetime: &OffsetDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+        let offset = datetime.offset().whole_seconds();
+        if offset % 60 != 0 {
+            return Err(S::Error::custom(format!("offset {} has seconds", datetime.offset())));
+        }
+        let zone = match offset {
+            0 => "Z".to_string(),
+            _ => format!("{}{:02}:{:02}", if offset < 0 { '-' } else { '+' }, offset.abs() / 3600, offset.abs() / 60 % 60),
+        };
+        let wall = format_datetime(datetime.unix_timestamp() + offset as i64, "").map_err(S::Error::custom)?;
+        let fraction = match datetime.nanosecond() {
+            0 => String::new(),
+            nanos => format!(".{:09}", nanos).trim_end_matches('0').to_string(),
+        };
+        serializer.serialize_str(&format!("{}{}{}", wall, fraction, zone))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OffsetDateTime, D::Error> {
+        let text = text(deserializer)?;
+        let invalid = || D::Error::custom(format!("invalid datetime {}", text));
+        let parsed = parse_datetime(&text).filter(|dt| !dt.zone.is_empty()).ok_or_else(invalid)?;
+        let offset = UtcOffset::from_whole_seconds(zone_offset(parsed.zone).ok_or_else(invalid)?).map_err(D::Error::custom)?;
+        let nanos = match parsed.fraction {
+            "" => 0,
+            fraction => format!("{:0<9}", &fraction[1..]).parse().map_err(D::Error::custom)?,
+        };
+        OffsetDateTime::from_unix_timestamp(parsed.seconds - offset.whole_seconds() as i64)
+            .and_then(|utc| utc.replace_nanosecond(nanos))
+            .map(|utc| utc.to_offset(offset))
+            .map_err(D::Error::custom)
+    }
+}
+
+/// `Duration` as a whole-second ISO 8601 duration such as `PT1H30M`.
+pub mod duration {
+    use super::*;
+    use crate::temporal::{format_duration, parse_duration};
+    use ::time::Duration;
+    use serde::ser::Error as _;
+    use serde::Serializer;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        if duration.subsec_nanoseconds() != 0 {
+            return Err(S::Error::custom(format!("{} is not a whole number of seconds", duration)));
+        }
+        serializer.serialize_str(&format_duration(duration.whole_seconds()))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        let text = text(deserializer)?;
+        parse_duration(&text)
+            .map(Duration::seconds)
+            .ok_or_else(|| D::Error::custom(format!("invalid duration {}", text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{decode, encode};
+    use ::time::{Date, Duration, Month, OffsetDateTime, UtcOffset};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Job {
+        #[serde(with = "super::date")]
+        day: Date,
+        #[serde(with = "super::datetime")]
+        started: OffsetDateTime,
+        #[serde(with = "super::duration")]
+        took: Duration,
+    }
+
+    #[test]
+    fn test_time_columns() {
+        let offset = UtcOffset::from_hms(2, 0, 0).unwrap();
+        let jobs: Vec<Job> = [(0, 5400), (1, 90), (3, 93600)]
+            .into_iter()
+            .map(|(day, took)| Job {
+                day: Date::from_calendar_date(2025, Month::December, 28 + day).unwrap(),
+                started: OffsetDateTime::from_unix_timestamp(1766917667 + day as i64 * 3600).unwrap().to_offset(offset),
+                took: Duration::seconds(took),
+            })
+            .collect();
+        let encoded = encode(&jobs).unwrap();
+        assert!(encoded.starts_with("# day:d2025-12-28 started:dt2025-12-28T12:27:47+02:00 took:du\n0 0 5400\n"));
+        let decoded: Vec<Job> = decode(&encoded).unwrap();
+        assert_eq!(decoded, jobs);
+
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Stamp {
+            #[serde(with = "super::datetime")]
+            at: OffsetDateTime,
+        }
+        let stamp = Stamp { at: OffsetDateTime::from_unix_timestamp_nanos(1_766_917_667_250_000_000).unwrap() };
+        let encoded = encode(&[&stamp]).unwrap();
+        assert_eq!(encoded, "# at:dt\n2025-12-28T10:27:47.25Z");
+        assert_eq!(decode::<Vec<Stamp>>(&encoded).unwrap(), vec![stamp]);
+    }
+}
@@ -0,0 +1,256 @@
+//! Async ZOON over tokio byte streams (feature `tokio`).
+//!
+//! [`ZoonCodec`] plugs into `tokio_util::codec::{FramedRead, FramedWrite}`
+//! and moves one row per frame: the decoder parses the header (and any
+//! later schema change) and yields rows as their lines arrive, the encoder
+//! writes a header before the first row and a widened one whenever a row no
+//! longer fits, like [`ndjson_to_zoon`](crate::ndjson_to_zoon).
+
+use crate::decode::{DecodeOptions, RowDecoder};
+use crate::encode::flatten_object;
+use crate::ndjson::Schema;
+use crate::{Result, ZoonError};
+use bytes::{BufMut, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::VecDeque;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio_util::codec::{Decoder, Encoder};
+
+/// Codec for tabular ZOON, one `serde_json::Value` row per frame.
+pub struct ZoonCodec {
+    max_line_length: usize,
+    decoder: RowDecoder,
+    /// Rows decoded but not yet returned; a `+N` header yields several.
+    pending: VecDeque<serde_json::Value>,
+    /// Bytes of the buffer already searched for a newline.
+    scanned: usize,
+    schema: Option<Schema>,
+}
+
+impl ZoonCodec {
+    /// A codec with the default limits, minus the row and input size caps
+    /// that make no sense for an unbounded stream.
+    pub fn new() -> Self {
+        ZoonCodec::with_options(DecodeOptions::streaming())
+    }
+
+    pub fn with_options(options: DecodeOptions) -> Self {
+        ZoonCodec {
+            max_line_length: options.max_line_length,
+            decoder: RowDecoder::new(options),
+            pending: VecDeque::new(),
+            scanned: 0,
+            schema: None,
+        }
+    }
+
+    fn push_line(&mut self, line: &[u8]) -> Result<()> {
+        let line = std::str::from_utf8(line).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+        let mut rows = Vec::new();
+        self.decoder.push_line(line, &mut rows)?;
+        self.pending.extend(rows);
+        Ok(())
+    }
+}
+
+impl Default for ZoonCodec {
+    fn default() -> Self {
+        ZoonCodec::new()
+    }
+}
+
+impl Decoder for ZoonCodec {
+    type Item = serde_json::Value;
+    type Error = ZoonError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<serde_json::Value>> {
+        loop {
+            if let Some(row) = self.pending.pop_front() {
+                return Ok(Some(row));
+            }
+            let Some(end) = src[self.scanned..].iter().position(|&b| b == b'\n') else {
+                // Fail before buffering a line that could never be accepted.
+                if src.len() > self.max_line_length {
+                    return Err(ZoonError::LimitExceeded(format!(
+                        "line of more than {} bytes exceeds max_line_length {}",
+                        src.len(),
+                        self.max_line_length
+                    )));
+                }
+                self.scanned = src.len();
+                return Ok(None);
+            };
+            let line = src.split_to(self.scanned + end + 1);
+            self.scanned = 0;
+            self.push_line(&line[..line.len() - 1])?;
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<serde_json::Value>> {
+        if let Some(row) = self.decode(src)? {
+            return Ok(Some(row));
+        }
+        if !src.is_empty() {
+            let line = src.split();
+            self.scanned = 0;
+            self.push_line(&line)?;
+        }
+        Ok(self.pending.pop_front())
+    }
+}
+
+impl<T: Serialize> Encoder<T> for ZoonCodec {
+    type Error = ZoonError;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<()> {
+        let value = serde_json::to_value(&item).map_err(|e| ZoonError::UnsupportedType(e.to_string()))?;
+        if !value.is_object() {
+            return Err(ZoonError::UnsupportedType("rows must be objects".into()));
+        }
+        let mut row = serde_json::Map::new();
+        flatten_object("", &value, &mut row);
+
+        let first = self.schema.is_none();
+        let schema = self.schema.get_or_insert_with(Schema::default);
+        if first || !schema.accepts(&row) {
+            schema.widen(&row);
+            put_line(dst, &schema.header());
+        }
+        put_line(dst, &schema.row(&row));
+        Ok(())
+    }
+}
+
+fn put_line(dst: &mut BytesMut, line: &str) {
+    dst.reserve(line.len() + 1);
+    dst.put_slice(line.as_bytes());
+    dst.put_u8(b'\n');
+}
+
+/// Reads a tabular ZOON document from `reader` with the default limits.
+pub async fn from_reader<R, T>(reader: R) -> Result<Vec<T>>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    from_reader_with_options(reader, &DecodeOptions::default()).await
+}
+
+/// Reads a tabular ZOON document from `reader`, enforcing `options`.
+pub async fn from_reader_with_options<R, T>(mut reader: R, options: &DecodeOptions) -> Result<Vec<T>>
+where
+    R: AsyncRead + Unpin,
+    T: DeserializeOwned,
+{
+    let mut codec = ZoonCodec::with_options(options.clone());
+    let mut buf = BytesMut::with_capacity(8 * 1024);
+    let mut rows = Vec::new();
+    let mut total = 0;
+
+    loop {
+        while let Some(row) = codec.decode(&mut buf)? {
+            rows.push(serde_json::from_value(row).map_err(|e| ZoonError::ParseError(e.to_string()))?);
+        }
+        let n = reader.read_buf(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        total += n;
+        if total > options.max_total_bytes {
+            return Err(ZoonError::LimitExceeded(format!(
+                "input of more than {} bytes exceeds max_total_bytes {}",
+                total, options.max_total_bytes
+            )));
+        }
+    }
+    while let Some(row) = codec.decode_eof(&mut buf)? {
+        rows.push(serde_json::from_value(row).map_err(|e| ZoonError::ParseError(e.to_string()))?);
+    }
+    Ok(rows)
+}
+
+/// Encodes `value` with [`encode`](crate::encode) and writes it to `writer`,
+/// followed by a newline.
+pub async fn to_writer<W, T>(mut writer: W, value: &T) -> Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    let zoon = crate::encode(value)?;
+    writer.write_all(zoon.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use serde::Deserialize;
+    use tokio_util::codec::{FramedRead, FramedWrite};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        id: i64,
+        name: String,
+        active: bool,
+    }
+
+    fn users() -> Vec<User> {
+        (1..=3)
+            .map(|id| User { id, name: format!("user {}", id), active: id % 2 == 1 })
+            .collect()
+    }
+
+    #[::tokio::test]
+    async fn test_reader_writer_roundtrip() {
+        let (client, server) = ::tokio::io::duplex(16);
+        let writer = ::tokio::spawn(async move { to_writer(client, &users()).await });
+        let decoded: Vec<User> = from_reader(server).await.unwrap();
+        writer.await.unwrap().unwrap();
+        assert_eq!(decoded, users());
+    }
+
+    #[::tokio::test]
+    async fn test_framed_rows() {
+        let (client, server) = ::tokio::io::duplex(64);
+        let writer = ::tokio::spawn(async move {
+            let mut sink = FramedWrite::new(client, ZoonCodec::new());
+            for user in users() {
+                sink.send(&user).await?;
+            }
+            sink.send(serde_json::json!({"id": 4, "name": "late", "active": true, "team": "core"})).await
+        });
+
+        let mut rows = FramedRead::new(server, ZoonCodec::new());
+        let mut decoded = Vec::new();
+        while let Some(row) = rows.next().await {
+            decoded.push(row.unwrap());
+        }
+        writer.await.unwrap().unwrap();
+
+        assert_eq!(decoded.len(), 4);
+        assert_eq!(decoded[0], serde_json::json!({"active": true, "id": 1, "name": "user 1"}));
+        assert_eq!(decoded[3]["team"], "core");
+    }
+
+    #[test]
+    fn test_codec_partial_lines() {
+        let mut codec = ZoonCodec::new();
+        let mut buf = BytesMut::from("# id:i+ name:s +1\nAl");
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(serde_json::json!({"id": 1, "name": null})));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"ice\nBob");
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(serde_json::json!({"id": 2, "name": "Alice"})));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), Some(serde_json::json!({"id": 3, "name": "Bob"})));
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+
+        let options = DecodeOptions { max_line_length: 8, ..DecodeOptions::default() };
+        let mut codec = ZoonCodec::with_options(options);
+        let mut buf = BytesMut::from("# name:s and no newline yet");
+        assert!(matches!(codec.decode(&mut buf), Err(ZoonError::LimitExceeded(_))));
+    }
+}
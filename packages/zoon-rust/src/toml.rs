@@ -0,0 +1,141 @@
+//! TOML conversion for inline documents (feature `toml`).
+//!
+//! A TOML table becomes one inline ZOON line (`server:{host=localhost port:3000}`)
+//! and back. Key order is preserved in both directions.
+
+use crate::decode::decode_inline;
+use crate::encode::{check_inline, encode_inline, Pretty};
+use crate::{Result, ZoonError};
+
+/// Converts a TOML document into an inline ZOON document.
+///
+/// Datetimes, multi-line strings and arrays of tables have no inline form and
+/// are reported as `ZoonError::UnsupportedType` with the path of the
+/// offending value.
+pub fn toml_to_zoon(input: &str) -> Result<String> {
+    let table: toml::Table = toml::from_str(input).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+    let obj = table_to_json("", table)?;
+    check_inline("", &serde_json::Value::Object(obj.clone()))?;
+    encode_inline(&obj, None, Pretty::Off)
+}
+
+/// Converts an inline ZOON document into TOML.
+///
+/// TOML has no null, so `key:~` values are reported as unsupported.
+pub fn zoon_to_toml(input: &str) -> Result<String> {
+    let table = match decode_inline(input.trim())? {
+        serde_json::Value::Object(obj) => json_to_table("", obj)?,
+        _ => return Err(ZoonError::InvalidFormat("top level must be an object".into())),
+    };
+    toml::to_string(&table).map_err(|e| ZoonError::UnsupportedType(e.to_string()))
+}
+
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+fn table_to_json(path: &str, table: toml::Table) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let mut obj = serde_json::Map::new();
+    for (k, v) in table {
+        let child = child_path(path, &k);
+        obj.insert(k, to_json(&child, v)?);
+    }
+    Ok(obj)
+}
+
+fn to_json(path: &str, value: toml::Value) -> Result<serde_json::Value> {
+    let unsupported = |what: &str| Err(ZoonError::UnsupportedType(format!("{} at {}", what, path)));
+    Ok(match value {
+        toml::Value::String(s) => serde_json::Value::String(s),
+        toml::Value::Integer(i) => i.into(),
+        toml::Value::Float(f) => match serde_json::Number::from_f64(f) {
+            Some(n) => serde_json::Value::Number(n),
+            None => return unsupported("non-finite float"),
+        },
+        toml::Value::Boolean(b) => serde_json::Value::Bool(b),
+        toml::Value::Datetime(dt) => return unsupported(&format!("datetime {}", dt)),
+        toml::Value::Array(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| match item {
+                    toml::Value::Table(_) => unsupported("array of tables"),
+                    item => to_json(path, item),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            serde_json::Value::Array(items)
+        }
+        toml::Value::Table(table) => serde_json::Value::Object(table_to_json(path, table)?),
+    })
+}
+
+fn json_to_table(path: &str, obj: serde_json::Map<String, serde_json::Value>) -> Result<toml::Table> {
+    let mut table = toml::Table::new();
+    for (k, v) in obj {
+        let child = child_path(path, &k);
+        table.insert(k, to_toml(&child, v)?);
+    }
+    Ok(table)
+}
+
+fn to_toml(path: &str, value: serde_json::Value) -> Result<toml::Value> {
+    Ok(match value {
+        serde_json::Value::Null => {
+            return Err(ZoonError::UnsupportedType(format!("null at {}", path)));
+        }
+        serde_json::Value::Bool(b) => toml::Value::Boolean(b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) => toml::Value::Integer(i),
+            None => toml::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        serde_json::Value::String(s) => toml::Value::String(s),
+        serde_json::Value::Array(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .map(|item| to_toml(path, item))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        serde_json::Value::Object(obj) => toml::Value::Table(json_to_table(path, obj)?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_to_zoon() {
+        let input = "name = \"My App\"\nports = [80, 443]\n\n[server]\nport = 3000\nhost = \"localhost\"\nratio = 0.5\n";
+        let zoon = toml_to_zoon(input).unwrap();
+        assert_eq!(zoon, "name=My_App ports:[80,443] server:{port:3000 host=localhost ratio:0.5}");
+
+        let back = zoon_to_toml(&zoon).unwrap();
+        assert_eq!(back, "name = \"My App\"\nports = [80, 443]\n\n[server]\nport = 3000\nhost = \"localhost\"\nratio = 0.5\n");
+    }
+
+    #[test]
+    fn test_toml_underscores() {
+        let input = "dir = \"/var/lib/my_app\"\nnames = [\"c_d\", \"e f\"]\n";
+        let zoon = toml_to_zoon(input).unwrap();
+        assert_eq!(zoon, r#"dir="/var/lib/my_app" names:["c_d",e_f]"#);
+        assert_eq!(zoon_to_toml(&zoon).unwrap(), input);
+    }
+
+    #[test]
+    fn test_toml_unsupported() {
+        let err = toml_to_zoon("[build]\nat = 1979-05-27T07:32:00Z\n").unwrap_err();
+        assert_eq!(err, ZoonError::UnsupportedType("datetime 1979-05-27T07:32:00Z at build.at".into()));
+
+        let err = toml_to_zoon("[[bin]]\nname = \"a\"\n").unwrap_err();
+        assert!(matches!(err, ZoonError::UnsupportedType(msg) if msg.contains("array of tables")));
+
+        let err = toml_to_zoon("motd = \"\"\"\nhello\nworld\"\"\"\n").unwrap_err();
+        assert!(matches!(err, ZoonError::UnsupportedType(msg) if msg.contains("multi-line")));
+
+        let err = zoon_to_toml("name=x value:~").unwrap_err();
+        assert_eq!(err, ZoonError::UnsupportedType("null at value".into()));
+    }
+}
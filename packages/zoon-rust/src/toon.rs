@@ -0,0 +1,302 @@
+//! Conversion between TOON tabular arrays and tabular ZOON.
+//!
+//! Handles TOON's tabular array form, with or without a key:
+//!
+//! ```text
+//! users[2]{id,name,role}:
+//!   1,Alice,admin
+//!   2,Bob,user
+//! ```
+//!
+//! Rows are handed to the regular tabular encoder, so the ZOON side gets the
+//! same enums, constants and aliases as [`crate::encode`].
+
+use crate::encode::flatten_object;
+use crate::{Result, ZoonError};
+
+/// Row delimiter used in TOON output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToonDelimiter {
+    #[default]
+    Comma,
+    Tab,
+    Pipe,
+}
+
+impl ToonDelimiter {
+    fn as_char(self) -> char {
+        match self {
+            ToonDelimiter::Comma => ',',
+            ToonDelimiter::Tab => '\t',
+            ToonDelimiter::Pipe => '|',
+        }
+    }
+
+    /// Marker written inside `[N]`; commas are the default and have none.
+    fn marker(self) -> &'static str {
+        match self {
+            ToonDelimiter::Comma => "",
+            ToonDelimiter::Tab => "\t",
+            ToonDelimiter::Pipe => "|",
+        }
+    }
+}
+
+/// Converts a TOON tabular array into tabular ZOON.
+///
+/// The array key, if any, is dropped: a ZOON table has no name.
+pub fn toon_to_zoon(input: &str) -> Result<String> {
+    let rows = parse_toon_table(input)?;
+    crate::encode(&serde_json::Value::Array(rows))
+}
+
+/// Converts tabular ZOON into a TOON tabular array.
+///
+/// Nested objects become dotted field names; array values have no place in a
+/// TOON table and are reported as unsupported.
+pub fn zoon_to_toon(input: &str, delimiter: ToonDelimiter) -> Result<String> {
+    let rows: Vec<serde_json::Value> = crate::decode(input)?;
+
+    let mut flat_rows = Vec::new();
+    let mut keys: Vec<String> = Vec::new();
+    for row in &rows {
+        let mut flat = serde_json::Map::new();
+        flatten_object("", row, &mut flat);
+        for key in flat.keys() {
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        flat_rows.push(flat);
+    }
+
+    let delim = delimiter.as_char();
+    let fields: Vec<String> = keys.iter().map(|k| format_key(k, delim)).collect();
+    let mut lines = vec![format!(
+        "[{}{}]{{{}}}:",
+        rows.len(),
+        delimiter.marker(),
+        fields.join(&delim.to_string())
+    )];
+    for flat in &flat_rows {
+        let mut cells = Vec::new();
+        for key in &keys {
+            let value = flat.get(key).unwrap_or(&serde_json::Value::Null);
+            cells.push(format_value(value, delim).ok_or_else(|| {
+                ZoonError::UnsupportedType(format!("non-primitive value in column {}", key))
+            })?);
+        }
+        lines.push(format!("  {}", cells.join(&delim.to_string())));
+    }
+    Ok(lines.join("\n"))
+}
+
+fn parse_toon_table(input: &str) -> Result<Vec<serde_json::Value>> {
+    let mut lines = input.lines().filter(|l| !l.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or_else(|| ZoonError::InvalidFormat("empty TOON document".into()))?
+        .trim();
+
+    let (count, delim, fields) = parse_toon_header(header)?;
+
+    let mut rows = Vec::new();
+    for line in lines {
+        if !line.starts_with([' ', '\t']) {
+            return Err(ZoonError::UnsupportedType(format!(
+                "only a single TOON tabular array is supported, found {:?}",
+                line
+            )));
+        }
+        let cells = split_cells(line.trim(), delim)?;
+        if cells.len() != fields.len() {
+            return Err(ZoonError::InvalidFormat(format!(
+                "TOON row has {} values, header declares {} fields",
+                cells.len(),
+                fields.len()
+            )));
+        }
+        let mut obj = serde_json::Map::new();
+        for (field, cell) in fields.iter().zip(cells) {
+            obj.insert(field.clone(), parse_cell(&cell));
+        }
+        rows.push(serde_json::Value::Object(obj));
+    }
+
+    if rows.len() != count {
+        return Err(ZoonError::InvalidFormat(format!(
+            "TOON header declares {} rows, found {}",
+            count,
+            rows.len()
+        )));
+    }
+    Ok(rows)
+}
+
+/// Parses `key[N<delim>]{f1,f2}:` into row count, delimiter and field names.
+fn parse_toon_header(header: &str) -> Result<(usize, char, Vec<String>)> {
+    let invalid = || ZoonError::InvalidFormat(format!("expected a TOON tabular header, found {:?}", header));
+
+    let open = header.find('[').ok_or_else(invalid)?;
+    let close = header[open..].find(']').map(|i| open + i).ok_or_else(invalid)?;
+    let bracket = &header[open + 1..close];
+    let (count, delim) = match bracket.chars().last() {
+        Some(c @ ('|' | '\t')) => (&bracket[..bracket.len() - 1], c),
+        _ => (bracket, ','),
+    };
+    let count = count.parse::<usize>().map_err(|_| invalid())?;
+
+    let rest = &header[close + 1..];
+    let fields = rest
+        .strip_prefix('{')
+        .and_then(|r| r.strip_suffix("}:"))
+        .ok_or_else(invalid)?;
+    let fields = split_cells(fields, delim)?;
+    Ok((count, delim, fields))
+}
+
+/// Splits a row on `delim`, honouring quoted cells. Quoted cells are returned
+/// with their quotes so `parse_cell` can tell them apart from bare tokens.
+fn split_cells(line: &str, delim: char) -> Result<Vec<String>> {
+    let mut cells = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if in_quotes => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c == delim && !in_quotes => {
+                cells.push(current.trim().to_string());
+                current.clear();
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(ZoonError::ParseError(format!("unterminated string in {:?}", line)));
+    }
+    cells.push(current.trim().to_string());
+    Ok(cells)
+}
+
+fn parse_cell(cell: &str) -> serde_json::Value {
+    if let Some(inner) = cell.strip_prefix('"').and_then(|c| c.strip_suffix('"')) {
+        return serde_json::Value::String(unescape(inner));
+    }
+    match cell {
+        "null" => serde_json::Value::Null,
+        "true" => serde_json::Value::Bool(true),
+        "false" => serde_json::Value::Bool(false),
+        _ => match serde_json::from_str::<serde_json::Number>(cell) {
+            Ok(n) => serde_json::Value::Number(n),
+            Err(_) => serde_json::Value::String(cell.to_string()),
+        },
+    }
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+fn format_value(value: &serde_json::Value, delim: char) -> Option<String> {
+    Some(match value {
+        serde_json::Value::Null => "null".to_string(),
+        serde_json::Value::Bool(b) => b.to_string(),
+        serde_json::Value::Number(n) => n.to_string(),
+        serde_json::Value::String(s) => format_string(s, delim),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => return None,
+    })
+}
+
+fn format_string(s: &str, delim: char) -> String {
+    let needs_quotes = s.is_empty()
+        || s.trim() != s
+        || matches!(s, "true" | "false" | "null")
+        || s.starts_with('-')
+        || s.starts_with(|c: char| c.is_ascii_digit())
+        || s.contains(|c: char| c == delim || c.is_control() || ":\"\\[]{}".contains(c));
+    if !needs_quotes {
+        return s.to_string();
+    }
+    let escaped = s
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+    format!("\"{}\"", escaped)
+}
+
+fn format_key(key: &str, delim: char) -> String {
+    let bare = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    if bare {
+        key.to_string()
+    } else {
+        format_string(key, delim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toon_to_zoon() {
+        let toon = "users[3]{id,name,role,active}:\n  1,Alice Smith,admin,true\n  2,Bob,user,false\n  3,\"Carol, Jr\",user,true";
+        let zoon = toon_to_zoon(toon).unwrap();
+        assert_eq!(zoon, "# id:i+ name:s role=admin|user active:b\nAlice_Smith admin 1\nBob user 0\nCarol,_Jr user 1");
+    }
+
+    #[test]
+    fn test_zoon_to_toon() {
+        let zoon = "# id:i+ name:s score:i\nAlice 10\nBob ~";
+        assert_eq!(zoon_to_toon(zoon, ToonDelimiter::Comma).unwrap(), "[2]{id,name,score}:\n  1,Alice,10\n  2,Bob,null");
+        assert_eq!(zoon_to_toon(zoon, ToonDelimiter::Pipe).unwrap(), "[2|]{id|name|score}:\n  1|Alice|10\n  2|Bob|null");
+    }
+
+    #[test]
+    fn test_toon_roundtrip() {
+        let toon = "[2|]{name|city|code}:\n  Alice|New York|\"007\"\n  Bob|Oslo|\"42\"";
+        let zoon = toon_to_zoon(toon).unwrap();
+        let rows: Vec<serde_json::Value> = crate::decode(&zoon).unwrap();
+        assert_eq!(rows[0]["city"], "New York");
+        assert_eq!(zoon_to_toon(&zoon, ToonDelimiter::Pipe).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_toon_errors() {
+        let err = toon_to_zoon("[3]{a,b}:\n  1,2").unwrap_err();
+        assert!(matches!(err, ZoonError::InvalidFormat(msg) if msg.contains("declares 3 rows")));
+
+        let err = toon_to_zoon("[1]{a,b}:\n  1").unwrap_err();
+        assert!(matches!(err, ZoonError::InvalidFormat(msg) if msg.contains("1 values")));
+
+        let err = toon_to_zoon("name: Alice").unwrap_err();
+        assert!(matches!(err, ZoonError::InvalidFormat(_)));
+    }
+}
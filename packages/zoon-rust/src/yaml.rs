@@ -0,0 +1,115 @@
+//! YAML conversion for inline documents (feature `yaml`).
+//!
+//! A YAML mapping becomes one inline ZOON line (`server:{host=localhost port:3000}`)
+//! and back. Key order is preserved in both directions.
+
+use crate::decode::decode_inline;
+use crate::encode::{check_inline, encode_inline, Pretty};
+use crate::{Result, ZoonError};
+use serde_yaml::Value as Yaml;
+
+/// Converts a YAML mapping into an inline ZOON document.
+///
+/// Tagged values, non-string keys, multi-line strings and nested sequences
+/// have no inline form and are reported as `ZoonError::UnsupportedType` with
+/// the path of the offending value.
+pub fn yaml_to_zoon(input: &str) -> Result<String> {
+    let yaml: Yaml = serde_yaml::from_str(input).map_err(|e| ZoonError::ParseError(e.to_string()))?;
+    match to_json("", yaml)? {
+        serde_json::Value::Object(obj) => {
+            check_inline("", &serde_json::Value::Object(obj.clone()))?;
+            encode_inline(&obj, None, Pretty::Off)
+        }
+        _ => Err(ZoonError::InvalidFormat("top level must be a YAML mapping".into())),
+    }
+}
+
+/// Converts an inline ZOON document into YAML.
+pub fn zoon_to_yaml(input: &str) -> Result<String> {
+    let value = decode_inline(input.trim())?;
+    serde_yaml::to_string(&value).map_err(|e| ZoonError::UnsupportedType(e.to_string()))
+}
+
+fn to_json(path: &str, yaml: Yaml) -> Result<serde_json::Value> {
+    let unsupported = |what: &str| Err(ZoonError::UnsupportedType(format!("{} at {}", what, display_path(path))));
+    Ok(match yaml {
+        Yaml::Null => serde_json::Value::Null,
+        Yaml::Bool(b) => serde_json::Value::Bool(b),
+        Yaml::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                i.into()
+            } else if let Some(u) = n.as_u64() {
+                u.into()
+            } else {
+                match n.as_f64().and_then(serde_json::Number::from_f64) {
+                    Some(f) => serde_json::Value::Number(f),
+                    None => return unsupported("non-finite number"),
+                }
+            }
+        }
+        Yaml::String(s) => serde_json::Value::String(s),
+        Yaml::Sequence(items) => {
+            let items = items
+                .into_iter()
+                .map(|item| to_json(path, item))
+                .collect::<Result<Vec<_>>>()?;
+            serde_json::Value::Array(items)
+        }
+        Yaml::Mapping(mapping) => {
+            let mut obj = serde_json::Map::new();
+            for (k, v) in mapping {
+                let key = match k {
+                    Yaml::String(key) => key,
+                    _ => return unsupported("non-string key"),
+                };
+                let child = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                obj.insert(key, to_json(&child, v)?);
+            }
+            serde_json::Value::Object(obj)
+        }
+        Yaml::Tagged(tagged) => return unsupported(&format!("tagged value {}", tagged.tag)),
+    })
+}
+
+fn display_path(path: &str) -> &str {
+    if path.is_empty() {
+        "top level"
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_yaml_to_zoon() {
+        let yaml = "server:\n  port: 3000\n  host: localhost\n  ssl: true\nname: My App\ntags: [web, api]\n";
+        let zoon = yaml_to_zoon(yaml).unwrap();
+        assert_eq!(zoon, "server:{port:3000 host=localhost ssl:y} name=My_App tags:[web,api]");
+
+        let back = zoon_to_yaml(&zoon).unwrap();
+        assert_eq!(back, "server:\n  port: 3000\n  host: localhost\n  ssl: true\nname: My App\ntags:\n- web\n- api\n");
+    }
+
+    #[test]
+    fn test_yaml_underscores() {
+        let yaml = "path: /var/lib/my_app\nnote: a_b c\ntags:\n- c_d\n- e f\n";
+        let zoon = yaml_to_zoon(yaml).unwrap();
+        assert_eq!(zoon, r#"path="/var/lib/my_app" note="a_b c" tags:["c_d",e_f]"#);
+        assert_eq!(zoon_to_yaml(&zoon).unwrap(), yaml);
+    }
+
+    #[test]
+    fn test_yaml_unsupported() {
+        let err = yaml_to_zoon("motd: |\n  hello\n  world\n").unwrap_err();
+        assert_eq!(err, ZoonError::UnsupportedType("multi-line string at motd".into()));
+
+        let err = yaml_to_zoon("when: !date 2025-01-01\n").unwrap_err();
+        assert!(matches!(err, ZoonError::UnsupportedType(msg) if msg.contains("tagged value")));
+
+        let err = yaml_to_zoon("- a\n- b\n").unwrap_err();
+        assert!(matches!(err, ZoonError::InvalidFormat(_)));
+    }
+}
@@ -0,0 +1,102 @@
+//! Runs the shared fixtures in the repository's `conformance/` directory.
+
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use zoon_format::{decode, encode, ZoonError};
+
+fn fixture_dir(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../../conformance").join(name)
+}
+
+fn fixtures(dir: &str, ext: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(fixture_dir(dir))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == ext))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "no .{} fixtures in {}", ext, dir);
+    paths
+}
+
+/// Reads a fixture without its final newline.
+fn read(path: &Path) -> String {
+    let text = std::fs::read_to_string(path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e));
+    text.strip_suffix('\n').unwrap_or(&text).to_string()
+}
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&read(path)).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn error_kind(err: &ZoonError) -> &'static str {
+    match err {
+        ZoonError::InvalidFormat(_) => "invalid-format",
+        ZoonError::ParseError(_) => "parse-error",
+        ZoonError::LimitExceeded(_) => "limit-exceeded",
+        ZoonError::UnsupportedType(_) | ZoonError::Io(_) => "unsupported",
+    }
+}
+
+/// Panics with every failure at once rather than stopping at the first.
+fn report(failures: Vec<String>) {
+    assert!(failures.is_empty(), "{} fixture(s) failed:\n\n{}", failures.len(), failures.join("\n\n"));
+}
+
+#[test]
+fn encode_fixtures() {
+    let mut failures = Vec::new();
+    for json_path in fixtures("encode", "json") {
+        let name = json_path.file_stem().unwrap().to_string_lossy().to_string();
+        let value = read_json(&json_path);
+        let expected = read(&json_path.with_extension("zoon"));
+
+        match encode(&value) {
+            Ok(actual) if actual == expected => {}
+            Ok(actual) => failures.push(format!("{}: encode\nexpected:\n{}\nactual:\n{}", name, expected, actual)),
+            Err(e) => failures.push(format!("{}: encode failed: {}", name, e)),
+        }
+        match decode::<Value>(&expected) {
+            Ok(decoded) if decoded == value => {}
+            Ok(decoded) => failures.push(format!("{}: decode\nexpected: {}\nactual:   {}", name, value, decoded)),
+            Err(e) => failures.push(format!("{}: decode failed: {}", name, e)),
+        }
+    }
+    report(failures);
+}
+
+#[test]
+fn decode_fixtures() {
+    let mut failures = Vec::new();
+    for zoon_path in fixtures("decode", "zoon") {
+        let name = zoon_path.file_stem().unwrap().to_string_lossy().to_string();
+        let expected = read_json(&zoon_path.with_extension("json"));
+        match decode::<Value>(&read(&zoon_path)) {
+            Ok(decoded) if decoded == expected => {}
+            Ok(decoded) => failures.push(format!("{}: decode\nexpected: {}\nactual:   {}", name, expected, decoded)),
+            Err(e) => failures.push(format!("{}: decode failed: {}", name, e)),
+        }
+    }
+    report(failures);
+}
+
+#[test]
+fn error_fixtures() {
+    let mut failures = Vec::new();
+    for error_path in fixtures("errors", "error") {
+        let name = error_path.file_stem().unwrap().to_string_lossy().to_string();
+        let expected = read(&error_path);
+        let zoon_path = error_path.with_extension("zoon");
+        let result = if zoon_path.exists() {
+            decode::<Value>(&read(&zoon_path)).map(|v| v.to_string())
+        } else {
+            encode(&read_json(&error_path.with_extension("json")))
+        };
+        match result {
+            Err(e) if error_kind(&e) == expected => {}
+            Err(e) => failures.push(format!("{}: expected {}, got {} ({})", name, expected, error_kind(&e), e)),
+            Ok(out) => failures.push(format!("{}: expected {}, got {}", name, expected, out)),
+        }
+    }
+    report(failures);
+}
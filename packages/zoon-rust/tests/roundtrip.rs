@@ -0,0 +1,147 @@
+//! Property-based round trips: `decode(encode(v)) == v` for values inside
+//! the ZOON data model.
+//!
+//! Strings may hold any character, and inline keys may hold spaces. Array
+//! items are integers or capitalised words, and nested objects are never
+//! empty.
+
+use proptest::prelude::*;
+use serde_json::{Map, Number, Value};
+
+fn key() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9]{0,5}"
+}
+
+/// Inline keys; spaces are allowed inside them.
+fn inline_key() -> impl Strategy<Value = String> {
+    "[a-z][a-z0-9 ]{0,5}[a-z0-9]"
+}
+
+/// Any text; printable ASCII with line breaks is drawn as often, so the
+/// characters that force quoting or escaping come up.
+fn text() -> impl Strategy<Value = Value> {
+    prop_oneof![any::<String>(), "[ -~\n\t\r\u{85}\u{2028}é]{0,40}"].prop_map(Value::String)
+}
+
+fn number() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        any::<i64>().prop_map(Value::from),
+        // Two decimal places, always with a fractional part.
+        (-1_000_000_000i64..1_000_000_000, 1u32..100).prop_map(|(int, frac)| {
+            let f: f64 = format!("{}.{:02}", int, frac).parse().unwrap();
+            Value::Number(Number::from_f64(f).unwrap())
+        }),
+    ]
+}
+
+fn array() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        prop::collection::vec(any::<i32>().prop_map(Value::from), 0..4),
+        prop::collection::vec("[A-Z][a-z]{1,6}".prop_map(Value::String), 0..4),
+    ]
+    .prop_map(Value::Array)
+}
+
+fn scalar() -> impl Strategy<Value = Value> {
+    prop_oneof![Just(Value::Null), any::<bool>().prop_map(Value::Bool), number(), text(), array()]
+}
+
+/// Inline documents: objects of scalars and nested objects.
+fn inline_object() -> impl Strategy<Value = Value> {
+    let leaf = prop::collection::vec((inline_key(), scalar()), 1..6).prop_map(|pairs| Value::Object(pairs.into_iter().collect()));
+    leaf.prop_recursive(3, 24, 6, |inner| {
+        prop::collection::vec((inline_key(), prop_oneof![scalar(), inner]), 1..6).prop_map(|pairs| Value::Object(pairs.into_iter().collect()))
+    })
+}
+
+/// How every row fills one column of a table.
+#[derive(Debug, Clone)]
+enum Column {
+    Int,
+    Id,
+    Number,
+    Bool,
+    Text,
+    Word(Vec<String>),
+    Mixed,
+    Array,
+    Nested(Vec<(String, Column)>),
+}
+
+fn column() -> impl Strategy<Value = Column> {
+    let leaf = prop_oneof![
+        Just(Column::Int),
+        Just(Column::Id),
+        Just(Column::Number),
+        Just(Column::Bool),
+        Just(Column::Text),
+        prop::collection::vec(prop_oneof!["[!-~]{1,8}", "\\PC{1,8}"], 1..5).prop_map(Column::Word),
+        Just(Column::Mixed),
+        Just(Column::Array),
+    ];
+    leaf.prop_recursive(2, 8, 3, |inner| prop::collection::vec((key(), inner), 1..4).prop_map(Column::Nested))
+}
+
+fn cell(column: &Column, row: usize) -> BoxedStrategy<Value> {
+    let nullable = |s: BoxedStrategy<Value>| prop_oneof![1 => Just(Value::Null), 4 => s].boxed();
+    match column {
+        Column::Int => nullable(any::<i64>().prop_map(Value::from).boxed()),
+        Column::Id => Just(Value::from(row as i64 + 1)).boxed(),
+        Column::Number => nullable(number().boxed()),
+        Column::Bool => nullable(any::<bool>().prop_map(Value::Bool).boxed()),
+        Column::Text => nullable(text().boxed()),
+        Column::Word(options) => nullable(prop::sample::select(options.clone()).prop_map(Value::String).boxed()),
+        Column::Mixed => nullable(prop_oneof![number(), text(), "[a-z]{1,3}".prop_map(Value::String), array(), any::<bool>().prop_map(Value::Bool)].boxed()),
+        Column::Array => array().boxed(),
+        Column::Nested(children) => object(children, row),
+    }
+}
+
+fn object(columns: &[(String, Column)], row: usize) -> BoxedStrategy<Value> {
+    let cells: Vec<BoxedStrategy<(String, Value)>> = columns
+        .iter()
+        .map(|(name, column)| {
+            let name = name.clone();
+            cell(column, row).prop_map(move |v| (name.clone(), v)).boxed()
+        })
+        .collect();
+    cells.prop_map(|pairs| Value::Object(pairs.into_iter().collect::<Map<_, _>>())).boxed()
+}
+
+/// Tabular documents: arrays of uniform objects.
+fn table() -> impl Strategy<Value = Value> {
+    (prop::collection::vec((key(), column()), 1..6), 1..12usize)
+        .prop_flat_map(|(columns, rows)| {
+            let rows: Vec<BoxedStrategy<Value>> = (0..rows).map(|i| object(&columns, i)).collect();
+            rows
+        })
+        .prop_map(Value::Array)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn inline_roundtrip(value in inline_object()) {
+        let encoded = zoon_format::encode(&value).unwrap();
+        let decoded: Value = zoon_format::decode(&encoded).unwrap();
+        prop_assert_eq!(&decoded, &value, "encoded: {}", encoded);
+
+        let options = zoon_format::EncodeOptions { pretty: zoon_format::Pretty::Nested, ..Default::default() };
+        let encoded = zoon_format::encode_with_options(&value, &options).unwrap();
+        let decoded: Value = zoon_format::decode(&encoded).unwrap();
+        prop_assert_eq!(decoded, value, "encoded: {}", encoded);
+    }
+
+    #[test]
+    fn tabular_roundtrip(value in table()) {
+        let encoded = zoon_format::encode(&value).unwrap();
+        let decoded: Value = zoon_format::decode(&encoded).unwrap();
+        prop_assert_eq!(decoded, value, "encoded: {}", encoded);
+    }
+
+    #[test]
+    fn decode_never_panics(input in "[#%@+:=!{}\\[\\]\"\\\\ ~_.,|\na-z0-9é-]{0,64}") {
+        let _ = zoon_format::decode::<Value>(&input);
+    }
+}